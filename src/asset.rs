@@ -0,0 +1,195 @@
+use std::io::{Read, Seek};
+
+use anyhow::{anyhow, bail, Context, Result};
+use unreal_asset::{exports::ExportBaseTrait, reader::ArchiveTrait, types::PackageIndex, Asset};
+
+use typed_path::Utf8UnixComponent as PakPathComponent;
+use typed_path::Utf8UnixPath as PakPath;
+use typed_path::Utf8UnixPathBuf as PakPathBuf;
+
+pub fn pak_path_to_game_path<P: AsRef<PakPath>>(pak_path: P) -> Result<String> {
+    let mut components = pak_path.as_ref().components();
+    Ok(match components.next() {
+        Some(PakPathComponent::Normal("Engine")) => match components.next() {
+            Some(PakPathComponent::Normal("Content")) => {
+                Some(PakPath::new("/Engine").join(components.as_path()))
+            }
+            Some(PakPathComponent::Normal("Plugins")) => {
+                let mut last = None;
+                loop {
+                    match components.next() {
+                        Some(PakPathComponent::Normal("Content")) => {
+                            break last.map(|plugin| {
+                                PakPath::new("/").join(plugin).join(components.as_path())
+                            })
+                        }
+                        Some(PakPathComponent::Normal(next)) => {
+                            last = Some(next);
+                        }
+                        _ => break None,
+                    }
+                }
+            }
+            _ => None,
+        },
+        Some(PakPathComponent::Normal(_)) => match components.next() {
+            Some(PakPathComponent::Normal("Content")) => {
+                Some(PakPath::new("/Game").join(components))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+    .with_context(|| format!("failed to normalize {}", pak_path.as_ref().as_str()))?
+    .to_string())
+}
+
+/// Resolves a pak mount point to the directory it mounts, relative to `../../../`. Besides the
+/// usual `../../../` the engine also loads paks mounted below it (e.g. `../../../FSD/Content/`)
+/// or at the root `/` with full paths.
+pub fn resolve_mount_point(mount_point: &str) -> Result<PakPathBuf> {
+    let relative = mount_point
+        .strip_prefix("../../../")
+        .or_else(|| mount_point.strip_prefix('/'))
+        .with_context(|| {
+            format!("Invalid mount point: {mount_point}, should begin with \"../../../\" or \"/\"")
+        })?;
+    let path = PakPath::new(relative);
+    if !path
+        .components()
+        .all(|c| matches!(c, PakPathComponent::Normal(_)))
+    {
+        bail!("Invalid mount point: {mount_point}, should not leave the game directory");
+    }
+    Ok(path.to_path_buf())
+}
+
+pub fn get_root_export<R: Read + Seek>(asset: &Asset<R>) -> Result<PackageIndex> {
+    for (i, e) in asset.asset_data.exports.iter().enumerate() {
+        let base = e.get_base_export();
+        if base.outer_index.index == 0 {
+            return Ok(PackageIndex::from_export(i as i32).unwrap());
+        }
+    }
+    bail!("no root export")
+}
+
+pub fn get_type<R: Read + Seek>(asset: &Asset<R>) -> Result<String> {
+    let root = get_root_export(asset)?;
+    let class = asset
+        .get_import(
+            asset
+                .get_export(root)
+                .unwrap()
+                .get_base_export()
+                .class_index,
+        )
+        .context("missing class import")?;
+    Ok(class.object_name.get_owned_content())
+}
+
+pub fn get_full_path<R: Read + Seek>(path: &str, asset: &Asset<R>) -> Result<String> {
+    let root = get_root_export(asset)?;
+    Ok(asset
+        .get_export(root)
+        .unwrap()
+        .get_base_export()
+        .object_name
+        .get_content(|c| format!("{path}.{c}")))
+}
+
+pub fn get_parent_path<R: Read + Seek>(asset: &Asset<R>) -> Result<Option<String>> {
+    let root = get_root_export(asset)?;
+    let export = asset.get_export(root).unwrap().get_base_export();
+
+    let mut import_index = export.super_index;
+
+    if import_index.index == 0 {
+        return Ok(None);
+    }
+
+    let mut components = vec![];
+
+    while import_index.is_import() {
+        let import = asset
+            .get_import(import_index)
+            .ok_or_else(|| anyhow!("missing import"))?;
+
+        components.insert(0, import.object_name.get_owned_content());
+
+        import_index = import.outer_index;
+    }
+    Ok(Some(components.join(".")))
+}
+
+/// Size the .uexp should have according to the export serial ranges in the header. Cooked
+/// packages store export data after the header and end the .uexp with a 4 byte package tag.
+pub fn expected_uexp_size<R: Read + Seek>(asset: &Asset<R>, header_size: u64) -> Option<u64> {
+    asset
+        .asset_data
+        .exports
+        .iter()
+        .map(|e| {
+            let base = e.get_base_export();
+            base.serial_offset + base.serial_size
+        })
+        .max()
+        .map(|end| (end as u64).saturating_sub(header_size) + 4)
+}
+
+/// Inverse of [`pak_path_to_game_path`] for `/Game` and `/Engine` packages.
+pub fn game_path_to_pak_path(game_path: &str) -> Option<String> {
+    if let Some(rest) = game_path.strip_prefix("/Game/") {
+        Some(format!("FSD/Content/{rest}"))
+    } else {
+        game_path
+            .strip_prefix("/Engine/")
+            .map(|rest| format!("Engine/Content/{rest}"))
+    }
+}
+
+/// Names of all packages imported by the asset, e.g. `/Game/Foo/Bar` or `/Script/Engine`.
+pub fn get_package_imports<R: Read + Seek>(asset: &Asset<R>) -> Vec<String> {
+    asset
+        .imports
+        .iter()
+        .filter(|i| i.outer_index.index == 0 && i.class_name.get_content(|c| c == "Package"))
+        .map(|i| i.object_name.get_owned_content())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mount_points() {
+        assert_eq!(resolve_mount_point("../../../").unwrap(), PakPathBuf::new());
+        assert_eq!(
+            resolve_mount_point("../../../FSD/Content/").unwrap(),
+            PakPathBuf::from("FSD/Content")
+        );
+        assert_eq!(resolve_mount_point("/").unwrap(), PakPathBuf::new());
+        assert_eq!(
+            resolve_mount_point("/FSD/").unwrap(),
+            PakPathBuf::from("FSD")
+        );
+    }
+
+    #[test]
+    fn mount_points_outside_game_directory() {
+        for mount_point in [
+            "../../",
+            "../../../../",
+            "/../x",
+            "../../../FSD/../../",
+            "//x",
+            "",
+        ] {
+            assert!(
+                resolve_mount_point(mount_point).is_err(),
+                "{mount_point} was accepted"
+            );
+        }
+    }
+}
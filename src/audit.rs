@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use anyhow::{anyhow, Context, Result};
+use typed_path::Utf8UnixPath as PakPath;
+
+use crate::asset::resolve_mount_point;
+use crate::index::IndexedFile;
+use crate::policy::Policy;
+use crate::source::{find_mod_files, get_mod_from_path, PakFile};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Mods {
+    pub mods: Vec<Mod>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Mod {
+    #[serde(rename = "ID")]
+    pub id: u32,
+    pub profile: ModProfile,
+    /// e.g. `Installed`, `UpdatePending` or `UninstallPending`, written by name or as the SDK's
+    /// enum value. `None` if missing or unreadable.
+    #[serde(default, deserialize_with = "lenient_mod_state")]
+    pub mod_state: Option<String>,
+    /// Number of local users subscribed to the mod. `None` if missing or unreadable.
+    #[serde(default, deserialize_with = "lenient_len")]
+    pub local_user_subscriptions: Option<usize>,
+}
+
+/// `ModState` values in the order of the mod.io SDK's enum.
+const MOD_STATES: &[&str] = &[
+    "InstallationPending",
+    "Installed",
+    "UpdatePending",
+    "Downloading",
+    "Extracting",
+    "UninstallPending",
+];
+
+fn lenient_mod_state<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) => Some(s),
+        Value::Number(n) => n
+            .as_u64()
+            .and_then(|n| MOD_STATES.get(n as usize))
+            .map(|s| s.to_string()),
+        _ => None,
+    })
+}
+
+fn lenient_len<'de, D: Deserializer<'de>>(d: D) -> Result<Option<usize>, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::Array(a) => Some(a.len()),
+        Value::Object(o) => Some(o.len()),
+        _ => None,
+    })
+}
+
+impl Mod {
+    /// Whether the mod is installed and subscribed to by a local user. Fields missing from older
+    /// state files or unreadable are assumed to be active.
+    pub fn is_active(&self) -> bool {
+        self.mod_state
+            .as_deref()
+            .is_none_or(|s| s == "Installed" || s == "UpdatePending")
+            && self.local_user_subscriptions.is_none_or(|s| s > 0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModProfile {
+    pub name: String,
+}
+
+/// A pak installed by a mod.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PakOwner {
+    pub mod_id: u32,
+    /// Path of the pak relative to the mod directory, `archive.zip/member.pak` for zipped paks.
+    pub pak: String,
+}
+
+impl PakOwner {
+    /// Mount priority of the pak, see [`pak_priority`].
+    pub fn priority(&self) -> u32 {
+        pak_priority(self.pak.rsplit(['/', '\\']).next().unwrap_or(&self.pak))
+    }
+}
+
+/// Mount priority the engine derives from a pak file name. Patch paks (`_P.pak`) are ranked
+/// 100 per chunk version above regular paks, where `Name_2_P.pak` has chunk version 3 and
+/// other patch paks version 1. Higher priority paks shadow the entries of lower ones.
+pub fn pak_priority(file_name: &str) -> u32 {
+    let lower = file_name.to_lowercase();
+    let Some(stem) = lower.strip_suffix("_p.pak") else {
+        return 0;
+    };
+    let chunk_version = stem
+        .rsplit_once('_')
+        .and_then(|(_, v)| v.parse::<u32>().ok())
+        .filter(|v| *v >= 1)
+        .map_or(1, |v| v + 1);
+    100 * chunk_version
+}
+
+/// A pak's copy of an asset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetCopy {
+    pub owner: PakOwner,
+    /// Asset path as spelled in this pak.
+    pub path: String,
+    /// Extension -> file. Files are only hashed for assets shipped by more than one pak and
+    /// stay `None` otherwise or when reading them failed.
+    pub files: BTreeMap<String, Option<IndexedFile>>,
+}
+
+/// Orders the copies of an asset from the one the game loads to the most shadowed. Paks of
+/// equal priority are assumed to be mounted in mod id and path order, the last one mounted
+/// winning.
+pub fn load_order(copies: &[AssetCopy]) -> Vec<&AssetCopy> {
+    let mut order = copies.iter().collect::<Vec<_>>();
+    order.sort_by_key(|c| std::cmp::Reverse((c.owner.priority(), &c.owner)));
+    order
+}
+
+/// How much an asset shipped by several paks matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Every copy is byte-identical, so it does not matter which one wins.
+    Identical,
+    /// The copies differ, but the policy lists the path as safe to conflict.
+    Benign,
+    Real,
+}
+
+/// Classifies an asset shipped by more than one pak.
+pub fn classify_conflict(path: &str, copies: &[AssetCopy], policy: &Policy) -> Severity {
+    // copies that could not be hashed are never considered identical
+    let hashed = copies.iter().all(|c| c.files.values().all(Option::is_some));
+    if hashed && copies.windows(2).all(|w| w[0].files == w[1].files) {
+        Severity::Identical
+    } else if policy.benign_conflicts.contains(path) {
+        Severity::Benign
+    } else {
+        Severity::Real
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AuditReport {
+    pub mod_names: HashMap<u32, String>,
+    /// Asset path (without extension) -> copies of it shipped by paks. Paths differing only in
+    /// case are the same asset to the game and are keyed by the first spelling found.
+    pub asset_owners: HashMap<String, Vec<AssetCopy>>,
+    /// Every audited pak, kept to read conflicting assets from.
+    pub paks: HashMap<PakOwner, PakFile>,
+    /// Asset path -> other spellings of it shipped by some mod.
+    pub case_variants: HashMap<String, BTreeSet<String>>,
+    /// Mods on disk that were skipped because they are not active.
+    pub inactive_mods: Vec<u32>,
+    /// Problems encountered while reading individual mods.
+    pub warnings: Vec<String>,
+}
+
+pub fn get_modio_dir() -> Result<PathBuf> {
+    match std::env::consts::OS {
+        "linux" => Ok(Path::new(&std::env::var("HOME")?).join(
+            ".local/share/Steam/steamapps/compatdata/548430/pfx/drive_c/users/Public/mod.io/",
+        )),
+        "windows" => Ok(PathBuf::from("C:/Users/Public/mod.io")),
+        _ => Err(anyhow!("unrecognized os")),
+    }
+}
+
+/// Audits the installed mods. Unless `include_inactive` is set, mods that are not active
+/// according to the mod.io state file (or missing from it) are skipped.
+pub fn audit<P: AsRef<Path>>(modio_path: P, include_inactive: bool) -> Result<AuditReport> {
+    let drg_modio_path = modio_path.as_ref().join("2475");
+    let state_path = drg_modio_path.join("metadata/state.json");
+    let mods_path = drg_modio_path.join("mods");
+    let state: Mods = serde_json::from_reader(BufReader::new(File::open(state_path)?))?;
+
+    let active = state
+        .mods
+        .iter()
+        .filter(|m| m.is_active())
+        .map(|m| m.id)
+        .collect::<HashSet<_>>();
+    let mut report = AuditReport {
+        mod_names: state
+            .mods
+            .into_iter()
+            .map(|m| (m.id, m.profile.name))
+            .collect(),
+        ..Default::default()
+    };
+    // lowercase path -> asset_owners key
+    let mut keys: HashMap<String, String> = HashMap::new();
+    for m in fs::read_dir(mods_path)? {
+        let m = m?;
+        let mod_id = m.file_name().to_string_lossy().parse::<u32>()?;
+        if !include_inactive && !active.contains(&mod_id) {
+            report.inactive_mods.push(mod_id);
+            continue;
+        }
+        let mod_files = find_mod_files(m.path())?;
+        if mod_files.is_empty() {
+            report
+                .warnings
+                .push(format!("could not find .pak in {}", m.path().display()));
+        }
+        for path in mod_files {
+            let relative = path
+                .strip_prefix(m.path())
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            let is_zip = path.extension().filter(|e| e == &"zip").is_some();
+            let files = match get_mod_from_path(path.clone()) {
+                Ok(files) => files,
+                Err(e) => {
+                    report
+                        .warnings
+                        .push(format!("error reading {}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            for (name, pak) in files.paks {
+                let pak_name = if is_zip {
+                    format!("{relative}/{name}")
+                } else {
+                    relative.clone()
+                };
+                match pak
+                    .open()
+                    .and_then(|mut reader| find_mod_assets(&mut reader))
+                {
+                    Ok(assets) => {
+                        for (asset, extensions) in assets {
+                            let key = keys
+                                .entry(asset.to_lowercase())
+                                .or_insert_with(|| asset.clone())
+                                .clone();
+                            if key != asset {
+                                report
+                                    .case_variants
+                                    .entry(key.clone())
+                                    .or_default()
+                                    .insert(asset.clone());
+                            }
+                            report.asset_owners.entry(key).or_default().push(AssetCopy {
+                                owner: PakOwner {
+                                    mod_id,
+                                    pak: pak_name.clone(),
+                                },
+                                path: asset,
+                                files: extensions.into_iter().map(|e| (e, None)).collect(),
+                            });
+                        }
+                    }
+                    Err(e) => report.warnings.push(format!(
+                        "error reading {}: {}",
+                        path.join(&name).display(),
+                        e
+                    )),
+                }
+                report.paks.insert(
+                    PakOwner {
+                        mod_id,
+                        pak: pak_name,
+                    },
+                    pak,
+                );
+            }
+        }
+    }
+    hash_conflicts(&mut report);
+    Ok(report)
+}
+
+/// Hashes the files of every asset shipped by more than one pak, opening each pak once.
+fn hash_conflicts(report: &mut AuditReport) {
+    let mut conflicts: HashMap<PakOwner, Vec<(String, usize)>> = HashMap::new();
+    for (asset, copies) in &report.asset_owners {
+        if copies.len() > 1 {
+            for (i, copy) in copies.iter().enumerate() {
+                conflicts
+                    .entry(copy.owner.clone())
+                    .or_default()
+                    .push((asset.clone(), i));
+            }
+        }
+    }
+    for (owner, copies) in conflicts {
+        let result = report.paks[&owner].open().and_then(|mut reader| {
+            let pak = repak::PakBuilder::new().reader(&mut reader)?;
+            let mount_point = resolve_mount_point(pak.mount_point())?;
+            for (asset, i) in copies {
+                let copy = &mut report.asset_owners.get_mut(&asset).unwrap()[i];
+                let path = PakPath::new(&copy.path)
+                    .strip_prefix(&mount_point)
+                    .with_context(|| format!("{} is outside the pak's mount point", copy.path))?;
+                for (extension, file) in copy.files.iter_mut() {
+                    let name = if extension.is_empty() {
+                        path.to_string()
+                    } else {
+                        format!("{path}.{extension}")
+                    };
+                    *file = Some(IndexedFile::new(&pak.get(&name, &mut reader)?));
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            report.warnings.push(format!(
+                "error hashing files of {} {}: {}",
+                owner.mod_id, owner.pak, e
+            ));
+        }
+    }
+}
+
+/// Asset path (without extension) -> extensions, for every file in a pak. Only the pak index is
+/// read.
+pub fn find_mod_assets<R: Read + Seek>(
+    reader: &mut R,
+) -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let pak = repak::PakBuilder::new().reader(reader)?;
+    let mount_point = resolve_mount_point(pak.mount_point())?;
+    let mut assets: BTreeMap<String, BTreeSet<String>> = Default::default();
+    for f in pak.files() {
+        let path = mount_point.join(&f);
+        assets
+            .entry(path.with_extension("").to_string())
+            .or_default()
+            .insert(path.extension().unwrap_or_default().to_owned());
+    }
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_mods() {
+        // shaped like a state.json written by the mod.io SDK, with both ModState encodings
+        let state: Mods = serde_json::from_str(
+            r#"{
+                "Mods": [
+                    {
+                        "ID": 1001,
+                        "ModState": "Installed",
+                        "Profile": { "id": 1001, "name": "Installed" },
+                        "LocalUserSubscriptions": ["1a2b3c"],
+                        "SizeOnDisk": 1024,
+                        "PathOnDisk": "C:/Users/Public/mod.io/2475/mods/1001"
+                    },
+                    {
+                        "ID": 1002,
+                        "ModState": 5,
+                        "Profile": { "id": 1002, "name": "Uninstall pending" },
+                        "LocalUserSubscriptions": ["1a2b3c"]
+                    },
+                    {
+                        "ID": 1003,
+                        "ModState": 2,
+                        "Profile": { "id": 1003, "name": "Unsubscribed" },
+                        "LocalUserSubscriptions": []
+                    },
+                    {
+                        "ID": 1004,
+                        "Profile": { "id": 1004, "name": "Older state file" }
+                    },
+                    {
+                        "ID": 1005,
+                        "ModState": { "unexpected": true },
+                        "Profile": { "id": 1005, "name": "Unreadable" },
+                        "LocalUserSubscriptions": "1a2b3c"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let active = state
+            .mods
+            .iter()
+            .map(|m| (m.id, m.is_active()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            active,
+            [
+                (1001, true),
+                (1002, false),
+                (1003, false),
+                (1004, true),
+                (1005, true)
+            ]
+        );
+        assert_eq!(state.mods[1].mod_state.as_deref(), Some("UninstallPending"));
+    }
+
+    #[test]
+    fn priority_from_file_name() {
+        assert_eq!(pak_priority("Foo.pak"), 0);
+        assert_eq!(pak_priority("Foo_P.pak"), 100);
+        assert_eq!(pak_priority("Foo_2_P.pak"), 300);
+        assert_eq!(pak_priority("Foo_0_P.pak"), 100);
+        assert_eq!(pak_priority("foo_p.PAK"), 100);
+        assert_eq!(pak_priority("Foo_bar_P.pak"), 100);
+    }
+
+    fn copy(mod_id: u32, pak: &str) -> AssetCopy {
+        AssetCopy {
+            owner: PakOwner {
+                mod_id,
+                pak: pak.to_owned(),
+            },
+            path: "FSD/Content/Foo".to_owned(),
+            files: Default::default(),
+        }
+    }
+
+    #[test]
+    fn load_order_ties() {
+        let copies = [
+            copy(1, "a.pak"),
+            copy(2, "b.pak"),
+            copy(3, "x.zip/c_P.pak"),
+            copy(3, "a.pak"),
+        ];
+        let order = load_order(&copies)
+            .into_iter()
+            .map(|c| (c.owner.mod_id, c.owner.pak.as_str()))
+            .collect::<Vec<_>>();
+        // patch pak first, then equal priorities by descending mod id and path
+        assert_eq!(
+            order,
+            [
+                (3, "x.zip/c_P.pak"),
+                (3, "a.pak"),
+                (2, "b.pak"),
+                (1, "a.pak")
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use drg_mod_tools::{index::GameIndex, version::parse_engine_version};
+
+/// Builds an index of the retail game pak for use with mod_lint and modio_audit --index
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// Game pak, usually FSD/Content/Paks/FSD-WindowsNoEditor.pak
+    pak: PathBuf,
+
+    /// Where to write the index
+    #[arg(short, long, default_value = "drg_index.json")]
+    output: PathBuf,
+
+    /// Engine version the game runs on
+    #[arg(long, value_parser = parse_engine_version, default_value = "4.27")]
+    engine_version: unreal_asset::engine_version::EngineVersion,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let open = || Ok(std::io::BufReader::new(std::fs::File::open(&args.pak)?));
+    let index = GameIndex::build(open, args.engine_version)?;
+    index.save(&args.output)?;
+    println!(
+        "indexed {} assets to {}",
+        index.assets.len(),
+        args.output.display()
+    );
+    Ok(())
+}
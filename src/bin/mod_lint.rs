@@ -1,16 +1,103 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
-    fs::File,
-    io::{BufRead, BufReader, Cursor, Read, Seek},
+    io::BufWriter,
+    path::{Path, PathBuf},
 };
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
 use colored::Colorize;
-use repak::PakBuilder;
-use unreal_asset::{exports::ExportBaseTrait, reader::ArchiveTrait, types::PackageIndex, Asset};
+use rayon::prelude::*;
+use repak::Compression;
+use tempfile::NamedTempFile;
+
+use drg_mod_tools::{
+    dependencies::DependencyGraph,
+    fix::fix_pak,
+    hierarchy::{to_dot, to_json, to_mermaid, Hierarchy},
+    index::{GameIndex, Origin},
+    lint::{Check, CheckLevels, JsonReport, Level},
+    lint_mod,
+    source::{find_mod_files, get_mod, get_modio_key, is_modio_url},
+    version::parse_engine_version,
+    LintOptions, LintReport, ModReport, Policy,
+};
+
+const EXIT_ERROR: i32 = 1;
+const EXIT_WARNINGS: i32 = 3;
+const EXIT_DENIED: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FixCompression {
+    None,
+    Zlib,
+}
 
-use typed_path::Utf8UnixComponent as PakPathComponent;
-use typed_path::Utf8UnixPath as PakPath;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum HierarchyFormat {
+    Text,
+    Dot,
+    Json,
+    Mermaid,
+}
+
+/// Scans mods for common problems as well as whether they should pass auto-verification
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// Mod .pak or .zip, mod.io mod URL, or directory to search for .pak and .zip files
+    #[arg(required = true)]
+    paths: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Only print the class hierarchy of all linted paks, in the given format
+    #[arg(long, value_enum)]
+    hierarchy_format: Option<HierarchyFormat>,
+
+    /// Only print the package dependency graph of all linted paks, with entry points and
+    /// orphaned assets
+    #[arg(long, conflicts_with = "hierarchy_format")]
+    dependencies: bool,
+
+    /// Auto-verify policy file (.toml or .json), defaults to the bundled policy
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// Engine version the game runs on, assets saved by any other version are flagged
+    #[arg(long, value_parser = parse_engine_version, default_value = "4.27")]
+    engine_version: unreal_asset::engine_version::EngineVersion,
+
+    /// Game index from build_index (or the game pak itself), used to tell overrides of game
+    /// assets from new assets, detect overrides that change an asset's class and check imports
+    #[arg(long)]
+    index: Option<PathBuf>,
+
+    /// Write a copy of the mod without extraneous files or asset registry, mounted at
+    /// ../../../, to this path and lint the copy instead
+    #[arg(long)]
+    fix: Option<PathBuf>,
+
+    /// Compression of the files written by --fix. The original compression method cannot be
+    /// read, so it is not kept
+    #[arg(long, value_enum, default_value_t = FixCompression::None, requires = "fix")]
+    compression: FixCompression,
+
+    /// Checks that do not affect the exit code (their findings are still printed)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    allow: Vec<Check>,
+
+    /// Checks that should fail the lint (exit code 4) rather than warn (exit code 3)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    deny: Vec<Check>,
+}
 
 fn main() -> Result<()> {
     // https://github.com/mackwic/colored/issues/110
@@ -19,522 +106,415 @@ fn main() -> Result<()> {
         let _varname = colored::control::set_virtual_terminal(true).unwrap_or(());
     }
 
-    if let Some(url) = std::env::args().nth(1) {
-        let mut reader = get_pak(&url)?;
-        let pak = PakBuilder::new().reader(&mut reader)?;
-        let mount_point = PakPath::new(pak.mount_point());
-        if let Ok(sanitized) = mount_point.strip_prefix("../../../") {
-            let valid_extensions =
-                vec!["uasset", "uexp", "umap", "ubulk", "ufont", "ini", "locres"]
+    let args = Args::parse();
+
+    let mut levels = CheckLevels::default();
+    levels.set(&args.allow, Level::Allow);
+    levels.set(&args.deny, Level::Deny);
+
+    let options = LintOptions {
+        policy: match &args.policy {
+            Some(path) => Policy::from_path(path)?,
+            None => Policy::default(),
+        },
+        engine_version: args.engine_version,
+        index: args
+            .index
+            .as_ref()
+            .map(|i| GameIndex::open(i, args.engine_version))
+            .transpose()?,
+    };
+
+    let mut inputs = vec![];
+    for path in &args.paths {
+        if std::path::Path::new(path).is_dir() {
+            inputs.extend(
+                find_mod_files(path)?
                     .into_iter()
-                    .collect::<BTreeSet<_>>();
-            let mut extraneous_files: BTreeSet<String> = Default::default();
-            let mut extensions: BTreeMap<String, BTreeSet<String>> = Default::default();
-            for f in pak.files() {
-                let path = PakPath::new(&f);
-                if let Some(ext) = path.extension() {
-                    if !valid_extensions.contains(ext) {
-                        extraneous_files.insert(f.to_owned());
+                    .map(|p| p.to_string_lossy().to_string()),
+            );
+        } else {
+            inputs.push(path.to_owned());
+        }
+    }
+
+    if let Some(out) = &args.fix {
+        if inputs.len() != 1 {
+            bail!("--fix takes exactly one mod, got {}", inputs.len());
+        }
+        let files = get_mod(&inputs[0])?;
+        let [(name, pak)] = &files.paks[..] else {
+            bail!(
+                "--fix only supports mods with a single pak, found {}",
+                files.paks.len()
+            );
+        };
+        // write next to the output and only replace it once done, as it may be the input
+        let dir = out
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut temp = NamedTempFile::new_in(dir)?;
+        let fix = fix_pak(
+            &mut pak.open()?,
+            BufWriter::new(temp.as_file_mut()),
+            &options.policy,
+            match args.compression {
+                FixCompression::None => None,
+                FixCompression::Zlib => Some(Compression::Zlib),
+            },
+        )?;
+        temp.persist(out)?;
+        eprintln!("fixed {name} -> {}", out.display());
+        if let Some(mount_point) = &fix.old_mount_point {
+            eprintln!("\tmount point {mount_point} -> ../../../");
+        }
+        for f in &fix.removed_files {
+            eprintln!("\tremoved {f}");
+        }
+        eprintln!(
+            "\tcompression: {}",
+            args.compression.to_possible_value().unwrap().get_name()
+        );
+        inputs = vec![out.to_string_lossy().to_string()];
+    }
+
+    // the same mod.io URL twice would download to the same file concurrently
+    let mut seen = std::collections::HashSet::new();
+    inputs.retain(|i| seen.insert(i.clone()));
+
+    if inputs.iter().filter(|i| is_modio_url(i)).count() > 1 {
+        // prompt for the key once up front instead of from every worker
+        get_modio_key()?;
+    }
+
+    let results = inputs
+        .par_iter()
+        .map(|input| get_mod(input).and_then(|files| lint_mod(files, &options)))
+        .collect::<Vec<_>>();
+
+    if let Some(format) = args.hierarchy_format {
+        let mut hierarchy = Hierarchy::default();
+        for (input, result) in inputs.iter().zip(&results) {
+            match result {
+                Ok(report) => {
+                    for pak in &report.paks {
+                        hierarchy.add(&pak.report);
                     }
-                    extensions
-                        .entry(path.with_extension("").to_string())
-                        .or_default()
-                        .insert(ext.to_owned());
-                } else {
-                    extraneous_files.insert(f.to_owned());
                 }
+                Err(e) => eprintln!("error linting {input}: {e:#}"),
             }
-            let extraneous_files = extraneous_files
-                .into_iter()
-                .map(|f| sanitized.join(f))
-                .filter(|f| f != "FSD/AssetRegistry.bin")
-                .collect::<BTreeSet<_>>();
-            if !extraneous_files.is_empty() {
-                println!("{}", "extraneous files:".bold());
-                for f in extraneous_files {
-                    println!("\t{f}");
+        }
+        let trees = hierarchy.trees();
+        let info = |id: &str| hierarchy.node_info(id, options.index.as_ref());
+        match format {
+            HierarchyFormat::Text => {
+                for tree in &trees {
+                    tree.print("");
                 }
             }
-            let mut split_pairs = BTreeSet::new();
-            let mut asset_types = BTreeMap::new();
-            let mut hierarchy: BTreeMap<String, BTreeSet<String>> = Default::default();
-            for (f, ext) in extensions {
-                let uasset = ext.contains("uasset");
-                let umap = ext.contains("umap");
-                let uexp = ext.contains("uexp");
-                if (umap || uasset) != uexp {
-                    for e in ext {
-                        split_pairs.insert(sanitized.join(&f).with_extension(e));
-                    }
-                } else if (umap || uasset) && uexp {
-                    let uasset = Cursor::new(pak.get(
-                        &if uasset {
-                            format!("{f}.uasset")
-                        } else {
-                            format!("{f}.umap")
-                        },
-                        &mut reader,
-                    )?);
-
-                    let pak_path = sanitized.join(&f);
-                    let path = pak_path_to_game_path(pak_path)?;
-
-                    let asset = unreal_asset::Asset::new(
-                        uasset,
-                        None,
-                        unreal_asset::engine_version::EngineVersion::VER_UE4_27,
-                        None,
-                        true,
-                    )
-                    .context("failed to parse asset")?;
-
-                    if let Some(parent_path) = get_parent_path(&asset)? {
-                        let full_path = get_full_path(&path, &asset)?;
-                        hierarchy.entry(parent_path).or_default().insert(full_path);
+            HierarchyFormat::Dot => print!("{}", to_dot(&trees, info)),
+            HierarchyFormat::Json => println!("{}", to_json(&trees, info)?),
+            HierarchyFormat::Mermaid => print!("{}", to_mermaid(&trees, info)),
+        }
+    } else if args.dependencies {
+        let mut graph = DependencyGraph::default();
+        for (input, result) in inputs.iter().zip(&results) {
+            match result {
+                Ok(report) => {
+                    for pak in &report.paks {
+                        graph.add(&pak.report);
                     }
-
-                    //println!("  {}", get_parent_path(&asset)?);
-                    asset_types.insert(
-                        get_full_path(&path, &asset)?, /*sanitized.join(f)*/
-                        get_type(&asset),
-                    );
                 }
+                Err(e) => eprintln!("error linting {input}: {e:#}"),
             }
-
-            println!("class hierarchy:");
-            let trees = build_trees(&hierarchy);
-            for tree in &trees {
-                tree.print("\t");
-            }
-
-            if !split_pairs.is_empty() {
-                println!("{}", "split asset pairs:".bold());
-                for f in split_pairs {
-                    println!("\t{f}");
+        }
+        match args.format {
+            Format::Text => {
+                if options.index.is_none() {
+                    eprintln!(
+                        "no --index given, overrides of game assets are reported as orphaned"
+                    );
                 }
+                print_dependencies(&graph);
             }
-            if !asset_types.is_empty() {
-                let auto_verified = [
-                    "SoundWave",
-                    "SoundCue",
-                    "SoundClass",
-                    "SoundMix",
-                    "MaterialInstanceConstant",
-                    "Material",
-                    "SkeletalMesh",
-                    "StaticMesh",
-                    "Texture2D",
-                    "AnimSequence",
-                    "Skeleton",
-                    "StringTable",
-                ]
-                .into_iter()
-                .collect::<BTreeSet<_>>();
-
-                let mut auto_verified_results = asset_types
-                    .into_iter()
-                    .map(|(f, t)| {
-                        let auto_verify = match &t {
-                            Ok(t) => {
-                                if auto_verified.contains(t.as_str()) {
-                                    AutoVerify::Pass
-                                } else {
-                                    AutoVerify::Fail
-                                }
-                            }
-                            _ => AutoVerify::Unknown,
-                        };
-                        let msg = match t {
-                            Ok(t) => AssetType::Known(t),
-                            Err(e) => AssetType::Unknown(format!("{e}")),
-                        };
-                        (auto_verify, msg, f)
-                    })
-                    .collect::<Vec<_>>();
-
-                auto_verified_results.sort();
-
-                println!(
-                    "{:12} {:30} {}",
-                    "auto-verify".bold(),
-                    "class".bold(),
-                    "asset path".bold()
-                );
-                for (a, m, f) in auto_verified_results {
-                    println!("{:^12} {:30} {}", a.output(), m.output(), f);
+            Format::Json => println!("{}", graph.to_json()?),
+        }
+    } else {
+        match args.format {
+            Format::Text => {
+                for (input, result) in inputs.iter().zip(&results) {
+                    if inputs.len() > 1 {
+                        println!("{}", input.bold().underline());
+                    }
+                    match result {
+                        Ok(report) => print_report(report),
+                        Err(e) => println!("{}", format!("error: {e:#}").red()),
+                    }
+                }
+                if inputs.len() > 1 {
+                    print_summary(&inputs, &results, &levels);
                 }
             }
-        } else {
-            return Err(anyhow!(
-                "Invalid mount point: {}, should begin with \"../../../\"",
-                pak.mount_point()
-            ));
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&JsonReport::new(
+                    inputs.iter().map(String::as_str).zip(&results),
+                    &levels
+                ))?
+            ),
         }
-    } else {
-        println!("Usage: {} <mod .pak or .zip>", env!("CARGO_BIN_NAME"))
+    }
+
+    let mod_levels = results
+        .iter()
+        .map(|r| r.as_ref().ok().map(|r| r.level(&levels)))
+        .collect::<Vec<_>>();
+    if mod_levels.contains(&Some(Level::Deny)) {
+        std::process::exit(EXIT_DENIED);
+    } else if mod_levels.contains(&None) {
+        std::process::exit(EXIT_ERROR);
+    } else if mod_levels.contains(&Some(Level::Warn)) {
+        std::process::exit(EXIT_WARNINGS);
     }
     Ok(())
 }
 
-#[derive(Debug, Ord, Eq, PartialEq, PartialOrd)]
-enum AutoVerify {
-    Pass,
-    Fail,
-    Unknown,
+fn print_summary(inputs: &[String], results: &[Result<ModReport>], levels: &CheckLevels) {
+    println!();
+    println!(
+        "{:8} {:50} {}",
+        "result".bold(),
+        "findings".bold(),
+        "mod".bold()
+    );
+    let mut passed = 0;
+    let mut warned = 0;
+    for (input, result) in inputs.iter().zip(results) {
+        let (status, findings) = match result {
+            Ok(report) => {
+                let findings = report
+                    .triggered_checks()
+                    .into_iter()
+                    .map(|c| c.name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let status = match report.level(levels) {
+                    Level::Allow => {
+                        passed += 1;
+                        "pass".green()
+                    }
+                    Level::Warn => {
+                        passed += 1;
+                        warned += 1;
+                        "warn".yellow()
+                    }
+                    Level::Deny => "deny".red(),
+                };
+                (status, findings)
+            }
+            Err(_) => ("error".red(), String::new()),
+        };
+        println!("{:8} {:50} {}", status, findings, input);
+    }
+    println!(
+        "{} passed ({} with warnings), {} failed",
+        passed,
+        warned,
+        inputs.len() - passed
+    );
 }
 
-impl AutoVerify {
-    fn output(&self) -> colored::ColoredString {
-        match self {
-            AutoVerify::Pass => "yes".green(),
-            AutoVerify::Fail => "no".red(),
-            AutoVerify::Unknown => "?".yellow(),
+fn print_dependencies(graph: &DependencyGraph) {
+    let entry_points = graph.entry_points();
+    if !entry_points.is_empty() {
+        println!("{}", "entry points:".bold());
+        for p in entry_points {
+            println!("\t{p}");
         }
     }
-}
-
-#[derive(Debug, Ord, Eq, PartialEq, PartialOrd)]
-enum AssetType {
-    Known(String),
-    Unknown(String),
-}
-
-impl AssetType {
-    fn output(&self) -> colored::ColoredString {
-        match self {
-            AssetType::Known(s) => s.normal(),
-            AssetType::Unknown(s) => s.yellow(),
+    let orphans = graph.orphans();
+    if !orphans.is_empty() {
+        println!("{}", "orphaned assets:".bold());
+        for p in orphans {
+            println!("\t{}", p.yellow());
         }
     }
-}
-
-fn pak_path_to_game_path<P: AsRef<PakPath>>(pak_path: P) -> Result<String> {
-    let mut components = pak_path.as_ref().components();
-    Ok(match components.next() {
-        Some(PakPathComponent::Normal("Engine")) => match components.next() {
-            Some(PakPathComponent::Normal("Content")) => {
-                Some(PakPath::new("/Engine").join(components.as_path()))
-            }
-            Some(PakPathComponent::Normal("Plugins")) => {
-                let mut last = None;
-                loop {
-                    match components.next() {
-                        Some(PakPathComponent::Normal("Content")) => {
-                            break last.map(|plugin| {
-                                PakPath::new("/").join(plugin).join(components.as_path())
-                            })
-                        }
-                        Some(PakPathComponent::Normal(next)) => {
-                            last = Some(next);
-                        }
-                        _ => break None,
-                    }
-                }
-            }
-            _ => None,
-        },
-        Some(PakPathComponent::Normal(_)) => match components.next() {
-            Some(PakPathComponent::Normal("Content")) => {
-                Some(PakPath::new("/Game").join(components))
+    println!("{}", "dependencies:".bold());
+    for (package, imports) in &graph.imports {
+        println!("\t{package}");
+        for import in imports {
+            if graph.imports.contains_key(import) {
+                println!("\t\t-> {import}");
+            } else {
+                println!("\t\t-> {}", import.dimmed());
             }
-            _ => None,
-        },
-        _ => None,
+        }
     }
-    .with_context(|| format!("failed to normalize {}", pak_path.as_ref().as_str()))?
-    .to_string())
 }
 
-fn get_root_export<R: Read + Seek>(asset: &Asset<R>) -> Result<PackageIndex> {
-    for (i, e) in asset.asset_data.exports.iter().enumerate() {
-        let base = e.get_base_export();
-        if base.outer_index.index == 0 {
-            return Ok(PackageIndex::from_export(i as i32).unwrap());
+fn print_report(report: &ModReport) {
+    if !report.non_pak_files.is_empty() {
+        println!("{}", "non-pak files:".bold());
+        for f in &report.non_pak_files {
+            println!("\t{f}");
         }
     }
-    bail!("no root export")
-}
-
-fn get_type<R: Read + Seek>(asset: &Asset<R>) -> Result<String> {
-    let root = get_root_export(asset)?;
-    let class = asset
-        .get_import(
-            asset
-                .get_export(root)
-                .unwrap()
-                .get_base_export()
-                .class_index,
-        )
-        .context("missing class import")?;
-    Ok(class.object_name.get_owned_content())
-}
-
-fn get_full_path<R: Read + Seek>(path: &str, asset: &Asset<R>) -> Result<String> {
-    let root = get_root_export(asset)?;
-    Ok(asset
-        .get_export(root)
-        .unwrap()
-        .get_base_export()
-        .object_name
-        .get_content(|c| format!("{path}.{c}")))
+    for pak in &report.paks {
+        if report.paks.len() > 1 {
+            println!("{}", pak.name.bold().underline());
+        }
+        print_pak_report(&pak.report);
+    }
 }
 
-fn get_parent_path<R: Read + Seek>(asset: &Asset<R>) -> Result<Option<String>> {
-    let root = get_root_export(asset)?;
-    let export = asset.get_export(root).unwrap().get_base_export();
-
-    let mut import_index = export.super_index;
-
-    if import_index.index == 0 {
-        return Ok(None);
+fn print_pak_report(report: &LintReport) {
+    if let Some(mount_point) = &report.mount_point {
+        println!(
+            "{} {mount_point} (expected ../../../)",
+            "unusual mount point:".bold()
+        );
     }
-
-    let mut components = vec![];
-
-    while import_index.is_import() {
-        let import = asset
-            .get_import(import_index)
-            .ok_or_else(|| anyhow!("missing import"))?;
-
-        components.insert(0, import.object_name.get_owned_content());
-
-        import_index = import.outer_index;
+    if !report.extraneous_files.is_empty() {
+        println!("{}", "extraneous files:".bold());
+        for f in &report.extraneous_files {
+            println!("\t{f}");
+        }
     }
-    Ok(Some(components.join(".")))
-}
-
-trait Reader: BufRead + Seek {}
-impl<T> Reader for T where T: BufRead + Seek {}
-
-fn get_pak(url: &str) -> Result<Box<dyn Reader>> {
-    let re = regex::Regex::new(
-        r"^https?://(mod\.io/g/drg/m/|drg\.(old\.)?mod\.io/)(?P<name_id>[^/#]+)$",
-    )
-    .unwrap();
-
-    let reader: Box<dyn Reader> = if let Some(captures) = re.captures(url) {
-        tokio::runtime::Builder::new_current_thread()
-            .enable_time()
-            .enable_io()
-            .build()
-            .unwrap()
-            .block_on(async { get_modio_mod(captures.name("name_id").unwrap().as_str()).await })?
-    } else {
-        Box::new(BufReader::new(File::open(url)?))
-    };
 
-    get_pak_from_data(reader)
-}
-
-fn get_pak_from_data(mut data: Box<dyn Reader>) -> Result<Box<dyn Reader>> {
-    if let Ok(mut archive) = zip::ZipArchive::new(&mut data) {
-        (0..archive.len())
-            .map(|i| -> Result<Option<Box<dyn Reader>>> {
-                let mut file = archive.by_index(i)?;
-                match file.enclosed_name() {
-                    Some(p) => {
-                        if file.is_file() && p.extension().filter(|e| e == &"pak").is_some() {
-                            let mut buf = vec![];
-                            file.read_to_end(&mut buf)?;
-                            Ok(Some(Box::new(Cursor::new(buf))))
-                        } else {
-                            Ok(None)
-                        }
-                    }
-                    None => Ok(None),
-                }
-            })
-            .find_map(|e| e.transpose())
-            .ok_or_else(|| anyhow!("Zip does not contain pak"))?
-    } else {
-        data.rewind()?;
-        Ok(data)
+    println!("class hierarchy:");
+    for tree in &report.class_trees() {
+        tree.print("\t");
     }
-}
-
-fn get_modio_key() -> Result<String> {
-    use directories::BaseDirs;
-    let key_path = if let Some(base_dirs) = BaseDirs::new() {
-        let dir = base_dirs.config_dir().join(env!("CARGO_PKG_NAME"));
-        Some((dir.join("modio_key.txt"), dir))
-    } else {
-        eprintln!("could not determine config path to save key");
-        None
-    };
 
-    let key = key_path.as_ref().and_then(|p| {
-        std::fs::read_to_string(&p.0)
-            .ok()
-            .map(|k| k.trim().to_owned())
-    });
-    Ok(if let Some(key) = key {
-        key
-    } else {
-        println!("No saved modio API key found, please generate one by going to https://mod.io/me/access#api and pasting it here");
-        let key = rpassword::prompt_password("API key: ")?;
-        if let Some(key_path) = key_path {
-            std::fs::create_dir_all(&key_path.1)?;
-            println!("writing modio API key to {}", key_path.0.display());
-            std::fs::write(key_path.0, &key)?;
+    let (overrides, new): (Vec<_>, Vec<_>) = report
+        .origins
+        .iter()
+        .partition(|(_, origin)| matches!(origin, Origin::Override { .. }));
+    if !overrides.is_empty() {
+        println!("{}", "overridden game assets:".bold());
+        for (path, origin) in overrides {
+            if let Origin::Override { original_class } = origin {
+                println!(
+                    "\t{path} ({})",
+                    original_class.as_deref().unwrap_or("unknown class")
+                );
+            }
         }
-        key
-    })
-}
+    }
+    if !new.is_empty() {
+        println!("{}", "new assets:".bold());
+        for (path, _) in new {
+            println!("\t{path}");
+        }
+    }
 
-const MODIO_DRG_ID: u32 = 2475;
-async fn get_modio_mod(name_id: &str) -> Result<Box<dyn Reader>> {
-    let modio = modio::Modio::new(modio::Credentials::new(get_modio_key()?))?;
-
-    use modio::filter::Eq;
-
-    let mut mods = modio
-        .game(MODIO_DRG_ID)
-        .mods()
-        .search(modio::mods::filters::NameId::eq(name_id))
-        .collect()
-        .await?;
-    if mods.len() > 1 {
-        Err(anyhow!(
-            "multiple mods returned for mod name_id {}",
-            name_id,
-        ))
-    } else if let Some(mod_) = mods.pop() {
-        let file = mod_
-            .modfile
-            .ok_or_else(|| anyhow!("mod {name_id} does not have an associated modfile"))?;
-
-        let filename = file.filename.to_owned();
+    if !report.class_changes.is_empty() {
         println!(
-            "downloading mod {} file_id={} to {}...",
-            name_id, file.id, filename
+            "{}",
+            "overrides with a different class than the original:".bold()
         );
+        for c in &report.class_changes {
+            println!("\t{} ({} -> {})", c.path, c.original_class, c.class.red());
+        }
+    }
 
-        use futures_util::TryStreamExt;
-        use tokio::io::AsyncWriteExt;
-
-        let download_bar = indicatif::ProgressBar::new(file.filesize);
-        download_bar.set_style(indicatif::ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?.progress_chars("#>-"));
-
-        let mut stream = Box::pin(
-            modio
-                .download(modio::download::DownloadAction::FileObj(Box::new(file)))
-                .stream(),
-        );
-        let mut cursor = Cursor::new(vec![]);
-        let mut file = tokio::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(filename)
-            .await?;
-        while let Some(bytes) = stream.try_next().await? {
-            cursor.write_all(&bytes).await?;
-            file.write_all(&bytes).await?;
-            download_bar.inc(bytes.len() as u64);
+    if !report.unresolved_imports.is_empty() {
+        println!("{}", "unresolved imports:".bold());
+        for i in &report.unresolved_imports {
+            println!("\t{} -> {}", i.path, i.import.red());
         }
+    }
 
-        Ok(Box::new(cursor))
-    } else {
-        Err(anyhow!("no mods returned for mod name_id {}", &name_id))
+    if !report.hierarchy_cycles.is_empty() {
+        println!("{}", "class hierarchy cycles:".bold());
+        for cycle in &report.hierarchy_cycles {
+            println!("\t{} -> {}", cycle.join(" -> "), cycle[0]);
+        }
     }
-}
 
-use std::collections::HashSet;
+    if !report.case_collisions.is_empty() {
+        println!("{}", "paths differing only in case:".bold());
+        for paths in &report.case_collisions {
+            println!(
+                "\t{}",
+                paths
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
 
-#[derive(Debug)]
-pub struct Node {
-    pub id: String,
-    pub children: Vec<Node>,
-}
-impl Node {
-    pub fn print(&self, prefix: &str) {
-        self.print_node(prefix, &mut vec![])
+    if !report.split_pairs.is_empty() {
+        println!("{}", "split asset pairs:".bold());
+        for f in &report.split_pairs {
+            println!("\t{f}");
+        }
     }
-    fn print_node(&self, prefix: &str, stack: &mut Vec<Edge>) {
-        print!("{prefix}");
-        for s in &*stack {
-            print!("{s}");
+    if !report.missing_bulk_data.is_empty() {
+        println!("{}", "overrides without a .ubulk although the original has one (fine if all data is stored inline):".bold());
+        for path in &report.missing_bulk_data {
+            println!("\t{path}");
         }
-
-        println!("{}", self.id);
-
-        if let Some((last, first)) = self.children.split_last() {
-            if let Some(last) = stack.last_mut() {
-                if *last == Edge::Corner {
-                    *last = Edge::None;
-                } else if *last == Edge::T {
-                    *last = Edge::Straight;
-                }
-            }
-
-            {
-                stack.push(Edge::T);
-                for child in first {
-                    child.print_node(prefix, stack);
-                }
-                stack.pop();
-            }
-
-            {
-                stack.push(Edge::Corner);
-                last.print_node(prefix, stack);
-                stack.pop();
-            }
-
-            if let Some(last) = stack.last_mut() {
-                if *last == Edge::Straight {
-                    *last = Edge::T;
-                }
-            }
+    }
+    if !report.uexp_mismatches.is_empty() {
+        println!("{}", "uexp size mismatches:".bold());
+        for m in &report.uexp_mismatches {
+            println!(
+                "\t{} ({}: expected {} bytes, found {})",
+                m.path,
+                if m.truncated() {
+                    "truncated"
+                } else {
+                    "too long"
+                },
+                m.expected_size,
+                m.actual_size
+            );
         }
     }
-}
-#[derive(PartialEq)]
-enum Edge {
-    None,
-    Straight,
-    Corner,
-    T,
-}
-impl std::fmt::Display for Edge {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Edge::None => write!(f, "    "),
-            Edge::Straight => write!(f, "│   "),
-            Edge::Corner => write!(f, "└── "),
-            Edge::T => write!(f, "├── "),
+    if !report.engine_version_mismatches.is_empty() {
+        println!("{}", "assets saved with the wrong engine version:".bold());
+        for m in &report.engine_version_mismatches {
+            println!(
+                "\t{} (UE {}, object version {}{})",
+                m.path,
+                m.detected.as_deref().unwrap_or("unknown"),
+                m.version.file_version_ue4,
+                m.version
+                    .file_version_ue5
+                    .map(|v| format!("/{v}"))
+                    .unwrap_or_default()
+            );
         }
     }
-}
-
-fn find_roots(edge_list: &BTreeMap<String, BTreeSet<String>>) -> Vec<&str> {
-    let parents = edge_list.keys().collect::<HashSet<_>>();
-    let children = edge_list.values().flatten().collect::<HashSet<_>>();
-
-    parents.difference(&children).map(|s| s.as_str()).collect()
-}
-
-fn build_node_recursively(id: &str, children_map: &BTreeMap<String, BTreeSet<String>>) -> Node {
-    let children = children_map
-        .get(id)
-        .map(|children| {
-            children
-                .iter()
-                .map(|child_id| build_node_recursively(child_id, children_map))
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-
-    Node {
-        id: id.to_string(),
-        children,
+    let export_errors = report
+        .assets
+        .iter()
+        .filter_map(|a| a.export_error.as_ref().map(|e| (&a.path, e)))
+        .collect::<Vec<_>>();
+    if !export_errors.is_empty() {
+        println!("{}", "failed to parse export data:".bold());
+        for (path, e) in export_errors {
+            println!("\t{path}: {}", e.yellow());
+        }
     }
-}
-
-pub fn build_trees(edge_list: &BTreeMap<String, BTreeSet<String>>) -> Vec<Node> {
-    let mut nodes = vec![];
-    for root in find_roots(edge_list) {
-        nodes.push(build_node_recursively(root, edge_list));
+    if !report.assets.is_empty() {
+        println!(
+            "{:12} {:30} {}",
+            "auto-verify".bold(),
+            "class".bold(),
+            "asset path".bold()
+        );
+        for a in &report.assets {
+            println!(
+                "{:^12} {:30} {}",
+                a.auto_verify.output(),
+                a.class.output(),
+                a.path
+            );
+        }
     }
-    nodes
 }
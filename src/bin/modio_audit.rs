@@ -1,44 +1,60 @@
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::BufReader;
-use std::path::{Path, PathBuf};
-
-use serde::Deserialize;
+use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf};
 
 use anyhow::{anyhow, Result};
+use clap::Parser;
+use unreal_asset::engine_version::EngineVersion;
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Mods {
-    mods: Vec<Mod>,
-}
+use drg_mod_tools::{
+    audit::{audit, classify_conflict, get_modio_dir, load_order, AssetCopy, PakOwner, Severity},
+    diff::{
+        diff_properties, overlapping_changes, read_pak_file_properties, ChangeKind, PakPackages,
+        PropertyChange,
+    },
+    index::{GameIndex, Origin},
+    source::PakFile,
+    version::parse_engine_version,
+    Policy,
+};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Mod {
-    #[serde(rename = "ID")]
-    id: u32,
-    profile: ModProfile,
-}
+/// Audits locally installed mods from mod.io for conflicts
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// mod.io directory, detected automatically if not specified
+    modio_path: Option<PathBuf>,
 
-#[derive(Debug, Deserialize)]
-struct ModProfile {
-    name: String,
-}
+    /// Game index from build_index (or the game pak itself), used to tell overrides of game
+    /// assets from new assets. With --diff, overrides are only compared to the original asset
+    /// if this is the game pak, as the index does not contain asset contents
+    #[arg(long)]
+    index: Option<PathBuf>,
 
-fn get_modio_dir() -> Result<PathBuf> {
-    match std::env::consts::OS {
-        "linux" => Ok(Path::new(&std::env::var("HOME")?).join(
-            ".local/share/Steam/steamapps/compatdata/548430/pfx/drive_c/users/Public/mod.io/",
-        )),
-        "windows" => Ok(PathBuf::from("C:/Users/Public/mod.io")),
-        _ => Err(anyhow!("unrecognized os")),
-    }
+    /// Engine version the game runs on, used to parse game and mod assets
+    #[arg(long, value_parser = parse_engine_version, default_value = "4.27")]
+    engine_version: EngineVersion,
+
+    /// Also audit mods that are disabled, unsubscribed or not fully installed
+    #[arg(long)]
+    include_inactive: bool,
+
+    /// Policy file (.toml or .json) listing benign conflicts, defaults to the bundled policy
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// Also list conflicts where every mod ships an identical copy of the asset
+    #[arg(long)]
+    show_identical: bool,
+
+    /// Parse conflicting assets and show which exports and properties each mod changed
+    #[arg(long)]
+    diff: bool,
 }
 
 fn main() -> Result<()> {
-    let modio_path = if let Some(modio_path) = std::env::args().nth(1) {
-        Ok(PathBuf::from(modio_path))
+    let args = Args::parse();
+
+    let modio_path = if let Some(modio_path) = args.modio_path {
+        Ok(modio_path)
     } else {
         get_modio_dir()
     };
@@ -51,77 +67,175 @@ fn main() -> Result<()> {
             }
         })
         .map_err(|e| anyhow!("Could not find mod.io directory ({e}). Try manually specifying it as an argument if you haven't already."))?;
-    let drg_modio_path = modio_path.join("2475");
-    let state_path = drg_modio_path.join("metadata/state.json");
-    let mods_path = drg_modio_path.join("mods");
-    let state: Mods = serde_json::from_reader(BufReader::new(File::open(state_path)?))?;
-    let mod_name_map = state
-        .mods
+    let index = args
+        .index
+        .as_ref()
+        .map(|i| GameIndex::open(i, args.engine_version))
+        .transpose()?;
+    let policy = match &args.policy {
+        Some(path) => Policy::from_path(path)?,
+        None => Policy::default(),
+    };
+    let mut game = args
+        .index
+        .as_ref()
+        .filter(|i| args.diff && i.extension().filter(|e| e == &"pak").is_some())
+        .map(|path| PakPackages::new(BufReader::new(File::open(path)?)))
+        .transpose()?;
+    let report = audit(modio_path, args.include_inactive)?;
+    for warning in &report.warnings {
+        println!("{warning}");
+    }
+    if !report.inactive_mods.is_empty() {
+        println!(
+            "skipped {} inactive mods, use --include-inactive to audit them",
+            report.inactive_mods.len()
+        );
+    }
+    let mut sorted = report
+        .asset_owners
         .into_iter()
-        .map(|m| (m.id, m.profile.name))
-        .collect::<HashMap<_, _>>();
-    let mut asset_owners: HashMap<String, Vec<u32>> = HashMap::new();
-    for m in fs::read_dir(mods_path)? {
-        let m = m?;
-        let mod_id = m.file_name().to_string_lossy().parse::<u32>()?;
-        if let Some(path) = find_pak(m.path())? {
-            match find_mod_assets(&path) {
-                Ok(files) => {
-                    for file in files {
-                        asset_owners.entry(file).or_default().push(mod_id);
-                    }
-                }
-                Err(e) => println!("error reading {}: {}", path.display(), e),
-            }
-        } else {
-            println!("could not find .pak in {}", m.path().display());
-        }
+        .map(|(path, copies)| {
+            let severity = (copies.len() > 1).then(|| classify_conflict(&path, &copies, &policy));
+            (path, copies, severity)
+        })
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|a| (a.2, a.1.len()));
+    let identical = sorted
+        .iter()
+        .filter(|a| a.2 == Some(Severity::Identical))
+        .count();
+    if identical > 0 && !args.show_identical {
+        println!("hiding {identical} conflicts between identical copies, use --show-identical to list them");
+        sorted.retain(|a| a.2 != Some(Severity::Identical));
     }
-    let mut sorted = asset_owners.into_iter().collect::<Vec<_>>();
-    sorted.sort_by_key(|a| a.1.len());
     for asset in sorted {
-        println!("{}", asset.0);
+        let origin = match index.as_ref().map(|i| i.origin(&asset.0)) {
+            Some(Origin::Override { original_class }) => format!(
+                " (overrides {})",
+                original_class.as_deref().unwrap_or("unknown class")
+            ),
+            Some(Origin::New) => " (new)".to_owned(),
+            None => String::new(),
+        };
+        let severity = match asset.2 {
+            Some(Severity::Identical) => " [identical copies]",
+            Some(Severity::Benign) => " [benign conflict]",
+            Some(Severity::Real) => " [conflict]",
+            None => "",
+        };
+        println!("{}{origin}{severity}", asset.0);
+        if let Some(variants) = report.case_variants.get(&asset.0) {
+            println!(
+                "\talso shipped as {}",
+                variants
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
         println!("\tmodified by:");
-        for mod_id in asset
-            .1
-            .into_iter()
-            .collect::<std::collections::HashSet<_>>()
-        {
-            println!("\t{} ({})", mod_id, mod_name_map[&mod_id]);
+        let order = load_order(&asset.1);
+        for (i, copy) in order.iter().enumerate() {
+            let owner = &copy.owner;
+            let status = if order.len() == 1 {
+                String::new()
+            } else if i == 0 {
+                if order[1].owner.priority() == owner.priority() {
+                    " [wins, tied priority so depends on mount order]".to_owned()
+                } else {
+                    " [wins]".to_owned()
+                }
+            } else {
+                format!(" [shadowed, priority {}]", owner.priority())
+            };
+            println!(
+                "\t{} ({}) {}{}",
+                owner.mod_id,
+                report
+                    .mod_names
+                    .get(&owner.mod_id)
+                    .map(String::as_str)
+                    .unwrap_or("not in state.json"),
+                owner.pak,
+                status
+            );
+        }
+        if args.diff && asset.2 == Some(Severity::Real) {
+            print_diff(
+                &asset.0,
+                &order,
+                &report.paks,
+                game.as_mut(),
+                args.engine_version,
+            );
         }
     }
     Ok(())
 }
 
-fn find_mod_assets<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
-    let pak = repak::PakBuilder::new().reader(&mut BufReader::new(File::open(path)?))?;
-    let mount_point = Path::new(pak.mount_point());
-    let files = pak
-        .files()
-        .into_iter()
-        .map(|f| -> Result<String> {
-            Ok(mount_point
-                .join(f)
-                .strip_prefix("../../../")?
-                .with_extension("")
-                .to_string_lossy()
-                .to_string())
-        })
-        .collect::<Result<Vec<_>>>()?;
-    Ok(files)
+fn print_diff(
+    path: &str,
+    order: &[&AssetCopy],
+    paks: &HashMap<PakOwner, PakFile>,
+    game: Option<&mut PakPackages<BufReader<File>>>,
+    engine_version: EngineVersion,
+) {
+    let label = |copy: &AssetCopy| format!("{} {}", copy.owner.mod_id, copy.owner.pak);
+    println!("\tdiff:");
+    let base = match game.map(|game| game.read_properties(path, engine_version)) {
+        Some(Ok(base)) => Some(base),
+        Some(Err(e)) => {
+            println!("\t\tnot comparing to the base game: {e:#}");
+            None
+        }
+        None => None,
+    };
+    let mut copies = vec![];
+    for copy in order {
+        match read_pak_file_properties(&paks[&copy.owner], &copy.path, engine_version) {
+            Ok(properties) => copies.push((*copy, properties)),
+            Err(e) => println!("\t\tfailed to read {}: {e:#}", label(copy)),
+        }
+    }
+    if let Some(base) = base {
+        let changes = copies
+            .iter()
+            .map(|(copy, properties)| {
+                let changes = diff_properties(&base, properties);
+                println!("\t\t{} vs base game:", label(copy));
+                print_changes(&changes);
+                changes
+            })
+            .collect::<Vec<_>>();
+        let overlapping = overlapping_changes(&changes);
+        if overlapping.is_empty() {
+            println!("\t\tchanges do not overlap and could be merged");
+        } else {
+            println!(
+                "\t\toverlapping changes: {}",
+                overlapping.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+    } else if let Some(((winner, winner_properties), rest)) = copies.split_first() {
+        for (copy, properties) in rest {
+            println!("\t\t{} vs {}:", label(copy), label(winner));
+            print_changes(&diff_properties(winner_properties, properties));
+        }
+    }
 }
 
-fn find_pak<P: AsRef<Path>>(dir: P) -> Result<Option<PathBuf>> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            if let Some(path) = find_pak(&path)? {
-                return Ok(Some(path));
-            }
-        } else if path.extension() == Some(std::ffi::OsStr::new("pak")) {
-            return Ok(Some(path));
-        }
+fn print_changes(changes: &[PropertyChange]) {
+    if changes.is_empty() {
+        println!("\t\t\tno property changes");
+    }
+    for change in changes {
+        let kind = match change.kind {
+            ChangeKind::Added => "+",
+            ChangeKind::Removed => "-",
+            ChangeKind::Changed => "~",
+        };
+        println!("\t\t\t{kind} {}", change.target());
     }
-    Ok(None)
 }
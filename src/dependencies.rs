@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::asset::pak_path_to_game_path;
+use crate::index::Origin;
+use crate::lint::LintReport;
+
+/// Package dependency graph of any number of paks, built from their import tables.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// Package shipped by a linted pak -> packages it imports.
+    pub imports: BTreeMap<String, BTreeSet<String>>,
+    /// Shipped packages that replace a game package. Only known when linting against a
+    /// [`GameIndex`](crate::index::GameIndex).
+    pub overrides: BTreeSet<String>,
+}
+
+impl DependencyGraph {
+    pub fn add(&mut self, report: &LintReport) {
+        for (package, imports) in &report.dependencies {
+            self.imports
+                .entry(package.clone())
+                .or_default()
+                .extend(imports.iter().cloned());
+        }
+        for (path, origin) in &report.origins {
+            if let Origin::Override { .. } = origin {
+                if let Ok(package) = pak_path_to_game_path(path) {
+                    self.overrides.insert(package);
+                }
+            }
+        }
+    }
+
+    /// Imported package -> shipped packages importing it.
+    pub fn imported_by(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut imported_by: BTreeMap<&str, BTreeSet<&str>> = Default::default();
+        for (package, imports) in &self.imports {
+            for import in imports {
+                imported_by
+                    .entry(import.as_str())
+                    .or_default()
+                    .insert(package.as_str());
+            }
+        }
+        imported_by
+    }
+
+    /// Shipped packages the game loads by itself because they take the place of one of its own.
+    pub fn entry_points(&self) -> BTreeSet<&str> {
+        self.imports
+            .keys()
+            .filter(|p| self.overrides.contains(*p))
+            .map(|p| p.as_str())
+            .collect()
+    }
+
+    /// Shipped packages that are neither entry points nor imported by another package.
+    pub fn orphans(&self) -> BTreeSet<&str> {
+        let imported_by = self.imported_by();
+        self.imports
+            .keys()
+            .filter(|p| !self.overrides.contains(*p))
+            .filter(|p| {
+                imported_by
+                    .get(p.as_str())
+                    .map(|i| i.iter().all(|i| i == p))
+                    .unwrap_or(true)
+            })
+            .map(|p| p.as_str())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct JsonDependencies<'a> {
+            imports: &'a BTreeMap<String, BTreeSet<String>>,
+            entry_points: BTreeSet<&'a str>,
+            orphans: BTreeSet<&'a str>,
+        }
+        Ok(serde_json::to_string_pretty(&JsonDependencies {
+            imports: &self.imports,
+            entry_points: self.entry_points(),
+            orphans: self.orphans(),
+        })?)
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Cursor, Read, Seek};
+
+use anyhow::{Context, Result};
+use repak::{PakBuilder, PakReader};
+use typed_path::Utf8UnixPath as PakPath;
+use unreal_asset::{
+    engine_version::EngineVersion,
+    exports::{ExportBaseTrait, ExportNormalTrait},
+    properties::{Property, PropertyDataTrait},
+};
+
+use crate::asset::resolve_mount_point;
+use crate::source::PakFile;
+
+/// Export name -> property name -> value, for every tagged property of an asset.
+pub type AssetProperties = BTreeMap<String, BTreeMap<String, Property>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A difference between two versions of an asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyChange {
+    pub export: String,
+    /// `None` if the whole export was added or removed.
+    pub property: Option<String>,
+    pub kind: ChangeKind,
+    /// New value of added and changed properties.
+    pub value: Option<Property>,
+}
+
+impl PropertyChange {
+    /// `Export.Property`, or just `Export` for whole exports.
+    pub fn target(&self) -> String {
+        match &self.property {
+            Some(property) => format!("{}.{property}", self.export),
+            None => self.export.clone(),
+        }
+    }
+
+    fn overlaps(&self, other: &PropertyChange) -> bool {
+        self.export == other.export
+            && match (&self.property, &other.property) {
+                (Some(a), Some(b)) => a == b && self.value != other.value,
+                (None, None) => {
+                    self.kind != ChangeKind::Removed || other.kind != ChangeKind::Removed
+                }
+                _ => true,
+            }
+    }
+}
+
+/// Inserts under `name`, or `name#2`, `name#3`, ... if it is taken. Export names are only unique
+/// per outer and static array elements share their property name.
+fn insert_unique<V>(map: &mut BTreeMap<String, V>, name: String, value: V) {
+    let mut key = name.clone();
+    let mut n = 1;
+    while map.contains_key(&key) {
+        n += 1;
+        key = format!("{name}#{n}");
+    }
+    map.insert(key, value);
+}
+
+pub fn read_properties(
+    uasset: Vec<u8>,
+    uexp: Vec<u8>,
+    engine_version: EngineVersion,
+) -> Result<AssetProperties> {
+    let asset = unreal_asset::Asset::new(
+        Cursor::new(uasset),
+        Some(Cursor::new(uexp)),
+        engine_version,
+        None,
+        false,
+    )
+    .context("failed to parse asset")?;
+
+    let mut exports = AssetProperties::new();
+    for export in &asset.asset_data.exports {
+        let mut properties = BTreeMap::new();
+        if let Some(normal) = export.get_normal_export() {
+            for property in &normal.properties {
+                insert_unique(
+                    &mut properties,
+                    property.get_name().get_owned_content(),
+                    property.clone(),
+                );
+            }
+        }
+        insert_unique(
+            &mut exports,
+            export.get_base_export().object_name.get_owned_content(),
+            properties,
+        );
+    }
+    Ok(exports)
+}
+
+/// A pak opened for reading packages by path, matched case-insensitively like the game does.
+pub struct PakPackages<R> {
+    pak: PakReader,
+    reader: R,
+    /// Lowercase package path (relative to `../../../` without extension) -> file path in the pak
+    /// (relative to the mount point, with extension).
+    packages: HashMap<String, String>,
+}
+
+impl<R: Read + Seek> PakPackages<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let pak = PakBuilder::new().reader(&mut reader)?;
+        let mount_point = resolve_mount_point(pak.mount_point())?;
+        let packages = pak
+            .files()
+            .into_iter()
+            .filter_map(|f| {
+                let path = mount_point.join(&f);
+                matches!(path.extension(), Some("uasset" | "umap"))
+                    .then(|| (path.with_extension("").as_str().to_lowercase(), f))
+            })
+            .collect();
+        Ok(Self {
+            pak,
+            reader,
+            packages,
+        })
+    }
+
+    /// Reads the properties of a package (path relative to `../../../` without extension).
+    pub fn read_properties(
+        &mut self,
+        path: &str,
+        engine_version: EngineVersion,
+    ) -> Result<AssetProperties> {
+        let package = self
+            .packages
+            .get(&path.to_lowercase())
+            .with_context(|| format!("{path} is not in the pak"))?;
+        let uasset = self.pak.get(package, &mut self.reader)?;
+        let uexp = self.pak.get(
+            PakPath::new(package).with_extension("uexp").as_str(),
+            &mut self.reader,
+        )?;
+        read_properties(uasset, uexp, engine_version)
+    }
+}
+
+/// [`PakPackages::read_properties`] for a pak that has not been opened yet.
+pub fn read_pak_file_properties(
+    pak_file: &PakFile,
+    path: &str,
+    engine_version: EngineVersion,
+) -> Result<AssetProperties> {
+    PakPackages::new(pak_file.open()?)?.read_properties(path, engine_version)
+}
+
+pub fn diff_properties(old: &AssetProperties, new: &AssetProperties) -> Vec<PropertyChange> {
+    let mut changes = vec![];
+    let exports = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    for export in exports {
+        match (old.get(export), new.get(export)) {
+            (Some(_), None) => changes.push(PropertyChange {
+                export: export.clone(),
+                property: None,
+                kind: ChangeKind::Removed,
+                value: None,
+            }),
+            (None, Some(_)) => changes.push(PropertyChange {
+                export: export.clone(),
+                property: None,
+                kind: ChangeKind::Added,
+                value: None,
+            }),
+            (Some(old), Some(new)) => {
+                let properties = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+                for property in properties {
+                    let kind = match (old.get(property), new.get(property)) {
+                        (Some(_), None) => ChangeKind::Removed,
+                        (None, Some(_)) => ChangeKind::Added,
+                        (Some(a), Some(b)) if a != b => ChangeKind::Changed,
+                        _ => continue,
+                    };
+                    changes.push(PropertyChange {
+                        export: export.clone(),
+                        property: Some(property.clone()),
+                        kind,
+                        value: new.get(property).cloned(),
+                    });
+                }
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    changes
+}
+
+/// Targets that more than one set of changes (each relative to the same base) modifies in
+/// different ways, so only one of them can take effect.
+pub fn overlapping_changes(changes: &[Vec<PropertyChange>]) -> BTreeSet<String> {
+    let mut overlapping = BTreeSet::new();
+    for (i, a) in changes.iter().enumerate() {
+        for b in &changes[i + 1..] {
+            for change in a {
+                if b.iter().any(|other| change.overlaps(other)) {
+                    overlapping.insert(change.target());
+                }
+            }
+        }
+    }
+    overlapping
+}
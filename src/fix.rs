@@ -0,0 +1,59 @@
+use std::io::{Read, Seek, Write};
+
+use crate::asset::resolve_mount_point;
+use crate::policy::Policy;
+use anyhow::Result;
+use repak::{Compression, PakBuilder};
+
+/// Changes made by [`fix_pak`].
+#[derive(Debug, Default)]
+pub struct FixReport {
+    /// Mount point of the original pak, if it was not `../../../`.
+    pub old_mount_point: Option<String>,
+    /// Dropped files, relative to `../../../`.
+    pub removed_files: Vec<String>,
+}
+
+/// Copies a mod pak to `writer` without extraneous files or asset registry, mounted at
+/// `../../../`. The pak version and path hash seed are kept. repak does not expose how the
+/// original entries were compressed, so the files are written with the given `compression`.
+pub fn fix_pak<R, W>(
+    reader: &mut R,
+    writer: W,
+    policy: &Policy,
+    compression: Option<Compression>,
+) -> Result<FixReport>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+{
+    let pak = PakBuilder::new().reader(reader)?;
+    let sanitized = resolve_mount_point(pak.mount_point())?;
+
+    let mut report = FixReport::default();
+    if pak.mount_point() != "../../../" {
+        report.old_mount_point = Some(pak.mount_point().to_owned());
+    }
+
+    let builder = match compression {
+        Some(compression) => PakBuilder::new().compression([compression]),
+        None => PakBuilder::new(),
+    };
+    let mut pak_writer = builder.writer(
+        writer,
+        pak.version(),
+        "../../../".to_owned(),
+        pak.path_hash_seed(),
+    );
+    for f in pak.files() {
+        let path = sanitized.join(&f);
+        if policy.is_extraneous(path.as_str()) || path.file_name() == Some("AssetRegistry.bin") {
+            report.removed_files.push(path.to_string());
+        } else {
+            pak_writer.write_file(path.as_str(), pak.get(&f, reader)?)?;
+        }
+    }
+    pak_writer.write_index()?.flush()?;
+
+    Ok(report)
+}
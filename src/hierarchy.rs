@@ -0,0 +1,409 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::asset::game_path_to_pak_path;
+use crate::index::GameIndex;
+use crate::lint::{AssetType, LintReport};
+
+#[derive(Debug)]
+pub struct Node {
+    pub id: String,
+    pub children: Vec<Node>,
+}
+impl Node {
+    pub fn print(&self, prefix: &str) {
+        self.print_node(prefix, &mut vec![])
+    }
+    fn print_node(&self, prefix: &str, stack: &mut Vec<Edge>) {
+        print!("{prefix}");
+        for s in &*stack {
+            print!("{s}");
+        }
+
+        println!("{}", self.id);
+
+        if let Some((last, first)) = self.children.split_last() {
+            if let Some(last) = stack.last_mut() {
+                if *last == Edge::Corner {
+                    *last = Edge::None;
+                } else if *last == Edge::T {
+                    *last = Edge::Straight;
+                }
+            }
+
+            {
+                stack.push(Edge::T);
+                for child in first {
+                    child.print_node(prefix, stack);
+                }
+                stack.pop();
+            }
+
+            {
+                stack.push(Edge::Corner);
+                last.print_node(prefix, stack);
+                stack.pop();
+            }
+
+            if let Some(last) = stack.last_mut() {
+                if *last == Edge::Straight {
+                    *last = Edge::T;
+                }
+            }
+        }
+    }
+}
+#[derive(PartialEq)]
+enum Edge {
+    None,
+    Straight,
+    Corner,
+    T,
+}
+impl std::fmt::Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edge::None => write!(f, "    "),
+            Edge::Straight => write!(f, "│   "),
+            Edge::Corner => write!(f, "└── "),
+            Edge::T => write!(f, "├── "),
+        }
+    }
+}
+
+fn find_roots(edge_list: &BTreeMap<String, BTreeSet<String>>) -> Vec<&str> {
+    let parents = edge_list.keys().collect::<BTreeSet<_>>();
+    let children = edge_list.values().flatten().collect::<HashSet<_>>();
+
+    parents
+        .into_iter()
+        .filter(|p| !children.contains(p))
+        .map(|s| s.as_str())
+        .collect()
+}
+
+fn build_node_recursively<'a>(
+    id: &'a str,
+    children_map: &'a BTreeMap<String, BTreeSet<String>>,
+    visited: &mut HashSet<&'a str>,
+) -> Node {
+    let mut children = vec![];
+    for child_id in children_map.get(id).into_iter().flatten() {
+        if visited.insert(child_id) {
+            children.push(build_node_recursively(child_id, children_map, visited));
+        }
+    }
+
+    Node {
+        id: id.to_string(),
+        children,
+    }
+}
+
+/// Builds a forest containing every node exactly once. Nodes only reachable through a cycle
+/// have no proper root, so the first unvisited node of each such component becomes one.
+pub fn build_trees(edge_list: &BTreeMap<String, BTreeSet<String>>) -> Vec<Node> {
+    let mut visited = HashSet::new();
+    let mut nodes = vec![];
+    let roots = find_roots(edge_list)
+        .into_iter()
+        .chain(edge_list.keys().map(|k| k.as_str()));
+    for root in roots {
+        if visited.insert(root) {
+            nodes.push(build_node_recursively(root, edge_list, &mut visited));
+        }
+    }
+    nodes
+}
+
+#[derive(PartialEq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
+fn visit_cycles<'a>(
+    id: &'a str,
+    edge_list: &'a BTreeMap<String, BTreeSet<String>>,
+    state: &mut HashMap<&'a str, VisitState>,
+    stack: &mut Vec<&'a str>,
+    cycles: &mut BTreeSet<Vec<String>>,
+) {
+    state.insert(id, VisitState::InProgress);
+    stack.push(id);
+    for child in edge_list.get(id).into_iter().flatten() {
+        match state.get(child.as_str()) {
+            None => visit_cycles(child, edge_list, state, stack, cycles),
+            Some(VisitState::InProgress) => {
+                let start = stack.iter().position(|s| s == child).unwrap();
+                let mut cycle = stack[start..]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                // rotate so the same cycle found from a different node compares equal
+                let min = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap();
+                cycle.rotate_left(min);
+                cycles.insert(cycle);
+            }
+            Some(VisitState::Done) => {}
+        }
+    }
+    stack.pop();
+    state.insert(id, VisitState::Done);
+}
+
+/// Every cycle in the edge list, each starting with its smallest node.
+pub fn find_cycles(edge_list: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<String>> {
+    let mut state = HashMap::new();
+    let mut cycles = BTreeSet::new();
+    for id in edge_list.keys() {
+        if !state.contains_key(id.as_str()) {
+            visit_cycles(id, edge_list, &mut state, &mut vec![], &mut cycles);
+        }
+    }
+    cycles.into_iter().collect()
+}
+
+/// Class hierarchy of any number of paks merged into one graph.
+#[derive(Debug, Default)]
+pub struct Hierarchy {
+    /// Superclass path -> subclass paths.
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+    /// Classes of assets shipped by the linted paks.
+    pub classes: BTreeMap<String, AssetType>,
+}
+
+impl Hierarchy {
+    pub fn add(&mut self, report: &LintReport) {
+        for (parent, children) in &report.hierarchy {
+            self.edges
+                .entry(parent.clone())
+                .or_default()
+                .extend(children.iter().cloned());
+        }
+        for asset in &report.assets {
+            self.classes.insert(asset.path.clone(), asset.class.clone());
+        }
+    }
+
+    pub fn trees(&self) -> Vec<Node> {
+        build_trees(&self.edges)
+    }
+
+    pub fn node_info(&self, id: &str, index: Option<&GameIndex>) -> NodeInfo {
+        if let Some(class) = self.classes.get(id) {
+            NodeInfo {
+                class: match class {
+                    AssetType::Known(class) => Some(class.clone()),
+                    AssetType::Unknown(_) => None,
+                },
+                location: Location::Mod,
+            }
+        } else if id.starts_with("/Script/") {
+            NodeInfo {
+                class: None,
+                location: Location::Native,
+            }
+        } else {
+            let package = id.split_once('.').map(|(p, _)| p).unwrap_or(id);
+            NodeInfo {
+                class: index
+                    .zip(game_path_to_pak_path(package))
+                    .and_then(|(index, path)| index.get(&path))
+                    .and_then(|asset| asset.class.clone()),
+                location: Location::Game,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Location {
+    /// Shipped by the mod.
+    Mod,
+    /// Asset from the base game.
+    Game,
+    /// Native class from a `/Script` package.
+    Native,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeInfo {
+    pub class: Option<String>,
+    pub location: Location,
+}
+
+#[derive(Serialize)]
+struct AnnotatedNode<'a> {
+    id: &'a str,
+    #[serde(flatten)]
+    info: NodeInfo,
+    children: Vec<AnnotatedNode<'a>>,
+}
+
+fn annotate<'a>(node: &'a Node, info: &impl Fn(&str) -> NodeInfo) -> AnnotatedNode<'a> {
+    AnnotatedNode {
+        id: &node.id,
+        info: info(&node.id),
+        children: node.children.iter().map(|c| annotate(c, info)).collect(),
+    }
+}
+
+fn walk<'a>(node: &'a Node, nodes: &mut Vec<&'a Node>, edges: &mut Vec<(&'a str, &'a str)>) {
+    nodes.push(node);
+    for child in &node.children {
+        edges.push((&node.id, &child.id));
+        walk(child, nodes, edges);
+    }
+}
+
+fn flatten(trees: &[Node]) -> (Vec<&Node>, Vec<(&str, &str)>) {
+    let mut nodes = vec![];
+    let mut edges = vec![];
+    for tree in trees {
+        walk(tree, &mut nodes, &mut edges);
+    }
+    (nodes, edges)
+}
+
+pub fn to_json(trees: &[Node], info: impl Fn(&str) -> NodeInfo) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&trees.iter().map(|t| annotate(t, &info)).collect::<Vec<_>>())
+}
+
+pub fn to_dot(trees: &[Node], info: impl Fn(&str) -> NodeInfo) -> String {
+    let quote = |s: &str| {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("\"{escaped}\"")
+    };
+    let (nodes, edges) = flatten(trees);
+    let mut out = String::from("digraph hierarchy {\n    rankdir=LR;\n    node [shape=box];\n");
+    for node in nodes {
+        let info = info(&node.id);
+        let label = match &info.class {
+            Some(class) => format!("{}\n{class}", node.id),
+            None => node.id.clone(),
+        };
+        let style = match info.location {
+            Location::Mod => "style=filled, fillcolor=lightblue",
+            Location::Game => "style=solid",
+            Location::Native => "style=dashed",
+        };
+        out.push_str(&format!(
+            "    {} [label={}, {style}];\n",
+            quote(&node.id),
+            quote(&label)
+        ));
+    }
+    for (parent, child) in edges {
+        out.push_str(&format!("    {} -> {};\n", quote(parent), quote(child)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+pub fn to_mermaid(trees: &[Node], info: impl Fn(&str) -> NodeInfo) -> String {
+    let (nodes, edges) = flatten(trees);
+    let ids = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), format!("n{i}")))
+        .collect::<BTreeMap<_, _>>();
+    let mut out = String::from("graph LR\n");
+    for node in &nodes {
+        let info = info(&node.id);
+        let mut label = node.id.replace('"', "#quot;");
+        if let Some(class) = &info.class {
+            label.push_str(&format!("<br/>{class}"));
+        }
+        let class = match info.location {
+            Location::Mod => ":::mod",
+            Location::Game => ":::game",
+            Location::Native => ":::native",
+        };
+        out.push_str(&format!(
+            "    {}[\"{label}\"]{class}\n",
+            ids[node.id.as_str()]
+        ));
+    }
+    for (parent, child) in edges {
+        out.push_str(&format!("    {} --> {}\n", ids[parent], ids[child]));
+    }
+    out.push_str("    classDef mod fill:#add8e6\n");
+    out.push_str("    classDef game fill:#ffffff\n");
+    out.push_str("    classDef native stroke-dasharray: 5 5\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(list: &[(&str, &str)]) -> BTreeMap<String, BTreeSet<String>> {
+        let mut edges: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (parent, child) in list {
+            edges
+                .entry(parent.to_string())
+                .or_default()
+                .insert(child.to_string());
+        }
+        edges
+    }
+
+    fn ids(nodes: &[Node], out: &mut Vec<String>) {
+        for node in nodes {
+            out.push(node.id.clone());
+            ids(&node.children, out);
+        }
+    }
+
+    #[test]
+    fn two_node_cycle() {
+        let edges = edges(&[("A", "B"), ("B", "A")]);
+        assert_eq!(find_cycles(&edges), vec![vec!["A", "B"]]);
+        let trees = build_trees(&edges);
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0].id, "A");
+        assert_eq!(trees[0].children[0].id, "B");
+        assert!(trees[0].children[0].children.is_empty());
+    }
+
+    #[test]
+    fn cycle_below_root() {
+        let edges = edges(&[("Root", "A"), ("A", "B"), ("B", "C"), ("C", "A")]);
+        assert_eq!(find_cycles(&edges), vec![vec!["A", "B", "C"]]);
+        let trees = build_trees(&edges);
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0].id, "Root");
+    }
+
+    #[test]
+    fn every_node_once() {
+        let edges = edges(&[
+            ("Root", "A"),
+            ("Root", "B"),
+            ("A", "C"),
+            ("B", "C"),
+            ("X", "Y"),
+            ("Y", "Z"),
+            ("Z", "X"),
+            ("Z", "W"),
+            ("S", "S"),
+        ]);
+        let mut out = vec![];
+        ids(&build_trees(&edges), &mut out);
+        out.sort();
+        assert_eq!(out, ["A", "B", "C", "Root", "S", "W", "X", "Y", "Z"]);
+    }
+
+    #[test]
+    fn cycles_rotated_and_deduplicated() {
+        // found from B first, still reported once starting at the smallest node
+        let edges = edges(&[("B", "C"), ("C", "A"), ("A", "B"), ("S", "S")]);
+        assert_eq!(find_cycles(&edges), vec![vec!["A", "B", "C"], vec!["S"]]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use rayon::prelude::*;
+use repak::PakBuilder;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use unreal_asset::engine_version::EngineVersion;
+
+use crate::asset::{get_type, resolve_mount_point};
+
+/// Bumped whenever the index layout changes so stale indexes are rejected.
+pub const INDEX_VERSION: u32 = 1;
+
+/// Paths, classes, sizes and hashes of every file in the retail game pak.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameIndex {
+    pub version: u32,
+    /// Asset path relative to `../../../` without extension, e.g. `FSD/Content/GameElements/Foo`.
+    pub assets: BTreeMap<String, IndexedAsset>,
+    /// Lowercase asset path -> key in `assets`, as the game looks up paths case-insensitively.
+    #[serde(skip)]
+    lowercase: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexedAsset {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// Extension -> file.
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub size: u64,
+    /// Hex encoded SHA-1 of the uncompressed file.
+    pub sha1: String,
+}
+
+impl IndexedFile {
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            sha1: hex::encode(Sha1::digest(data)),
+        }
+    }
+}
+
+/// Whether a mod file replaces a game asset or adds a new one.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Origin {
+    Override { original_class: Option<String> },
+    New,
+}
+
+impl GameIndex {
+    /// Indexes a game pak. `open` may be called many times while reading assets in parallel and
+    /// each call must return an independent reader.
+    pub fn build<R, F>(open: F, engine_version: EngineVersion) -> Result<Self>
+    where
+        R: Read + Seek,
+        F: Fn() -> Result<R> + Sync + Send,
+    {
+        let pak = PakBuilder::new().reader(&mut open()?)?;
+        let sanitized = resolve_mount_point(pak.mount_point())?;
+
+        let files = pak
+            .files()
+            .par_iter()
+            .map_init(open, |reader, f| -> Result<_> {
+                let reader = reader.as_mut().map_err(|e| anyhow!("{e:#}"))?;
+                let data = pak.get(f, reader)?;
+                let path = sanitized.join(f);
+                let ext = path.extension().unwrap_or_default().to_owned();
+                let class = if ext == "uasset" || ext == "umap" {
+                    unreal_asset::Asset::new(
+                        Cursor::new(&data[..]),
+                        None,
+                        engine_version,
+                        None,
+                        true,
+                    )
+                    .ok()
+                    .and_then(|asset| get_type(&asset).ok())
+                } else {
+                    None
+                };
+                Ok((
+                    path.with_extension("").to_string(),
+                    ext,
+                    class,
+                    IndexedFile::new(&data),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut assets: BTreeMap<String, IndexedAsset> = Default::default();
+        for (path, ext, class, file) in files {
+            let asset = assets.entry(path).or_default();
+            if class.is_some() {
+                asset.class = class;
+            }
+            asset.files.insert(ext, file);
+        }
+        Ok(Self {
+            version: INDEX_VERSION,
+            assets,
+            lowercase: Default::default(),
+        }
+        .with_lowercase())
+    }
+
+    fn with_lowercase(mut self) -> Self {
+        self.lowercase = self
+            .assets
+            .keys()
+            .map(|path| (path.to_lowercase(), path.clone()))
+            .collect();
+        self
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let index: Self = serde_json::from_reader(BufReader::new(
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?,
+        ))
+        .with_context(|| format!("failed to read index {}", path.display()))?;
+        if index.version != INDEX_VERSION {
+            bail!(
+                "index {} has version {}, expected {INDEX_VERSION}; rebuild it with build_index",
+                path.display(),
+                index.version
+            );
+        }
+        Ok(index.with_lowercase())
+    }
+
+    /// Loads an index, or builds one in memory if given the game pak itself.
+    pub fn open<P: AsRef<Path>>(path: P, engine_version: EngineVersion) -> Result<Self> {
+        let path = path.as_ref();
+        if path.extension().filter(|e| e == &"pak").is_some() {
+            Self::build(|| Ok(BufReader::new(File::open(path)?)), engine_version)
+                .with_context(|| format!("failed to index {}", path.display()))
+        } else {
+            Self::load(path)
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Looks up an asset by its path relative to `../../../` without extension, ignoring case.
+    pub fn get(&self, path: &str) -> Option<&IndexedAsset> {
+        self.assets.get(path).or_else(|| {
+            self.lowercase
+                .get(&path.to_lowercase())
+                .and_then(|path| self.assets.get(path))
+        })
+    }
+
+    pub fn origin(&self, path: &str) -> Origin {
+        match self.get(path) {
+            Some(asset) => Origin::Override {
+                original_class: asset.class.clone(),
+            },
+            None => Origin::New,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_ignores_case() {
+        let index: GameIndex = serde_json::from_str(
+            r#"{"version":1,"assets":{"FSD/Content/Weapons/Foo":{"class":"Blueprint","files":{}}}}"#,
+        )
+        .unwrap();
+        let index = index.with_lowercase();
+        assert!(index.get("FSD/Content/Weapons/Foo").is_some());
+        assert!(index.get("FSD/Content/weapons/foo").is_some());
+        assert_eq!(
+            index.origin("fsd/content/WEAPONS/Foo"),
+            Origin::Override {
+                original_class: Some("Blueprint".to_owned())
+            }
+        );
+        assert_eq!(index.origin("FSD/Content/Weapons/Bar"), Origin::New);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod asset;
+pub mod audit;
+pub mod dependencies;
+pub mod diff;
+pub mod fix;
+pub mod hierarchy;
+pub mod index;
+pub mod lint;
+pub mod policy;
+pub mod source;
+pub mod version;
+
+pub use lint::{lint_mod, lint_pak, LintOptions, LintReport, ModReport};
+pub use policy::Policy;
@@ -0,0 +1,664 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read, Seek},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use rayon::prelude::*;
+use repak::{PakBuilder, PakReader};
+use serde::Serialize;
+
+use typed_path::Utf8UnixPath as PakPath;
+use unreal_asset::engine_version::EngineVersion;
+
+use crate::asset::{
+    expected_uexp_size, game_path_to_pak_path, get_full_path, get_package_imports, get_parent_path,
+    get_type, pak_path_to_game_path, resolve_mount_point,
+};
+use crate::hierarchy::{build_trees, find_cycles, Node};
+use crate::index::{GameIndex, Origin};
+use crate::policy::Policy;
+use crate::source::ModFiles;
+use crate::version::PackageVersion;
+
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoVerify {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+impl AutoVerify {
+    pub fn output(&self) -> colored::ColoredString {
+        match self {
+            AutoVerify::Pass => "yes".green(),
+            AutoVerify::Fail => "no".red(),
+            AutoVerify::Unknown => "?".yellow(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub enum AssetType {
+    #[serde(rename = "class")]
+    Known(String),
+    #[serde(rename = "error")]
+    Unknown(String),
+}
+
+impl AssetType {
+    pub fn output(&self) -> colored::ColoredString {
+        match self {
+            AssetType::Known(s) => s.normal(),
+            AssetType::Unknown(s) => s.yellow(),
+        }
+    }
+}
+
+/// Auto-verification verdict for a single asset, ordered by verdict, class, then path.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct AssetVerdict {
+    pub auto_verify: AutoVerify,
+    #[serde(flatten)]
+    pub class: AssetType,
+    pub path: String,
+    /// Set if the header parsed but the export data in the .uexp did not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_error: Option<String>,
+}
+
+/// An asset whose .uexp size disagrees with the export serial sizes in its header.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct UexpMismatch {
+    pub path: String,
+    pub expected_size: u64,
+    pub actual_size: u64,
+}
+
+/// An asset saved by a different engine release than the one the game runs on.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct EngineVersionMismatch {
+    pub path: String,
+    #[serde(flatten)]
+    pub version: PackageVersion,
+    /// Engine releases matching the package version, if known.
+    pub detected: Option<String>,
+}
+
+/// An override of a game asset whose root export has a different class than the original.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct ClassChange {
+    pub path: String,
+    pub original_class: String,
+    pub class: String,
+}
+
+/// A `/Game` package import found neither in the pak nor in the game index.
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct UnresolvedImport {
+    pub path: String,
+    pub import: String,
+}
+
+impl UexpMismatch {
+    pub fn truncated(&self) -> bool {
+        self.actual_size < self.expected_size
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LintReport {
+    /// Mount point of the pak if it is valid but not the usual `../../../`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount_point: Option<String>,
+    /// Files that should not be shipped in a mod pak.
+    pub extraneous_files: BTreeSet<String>,
+    /// Groups of files whose paths differ only in case, which the game treats as the same file.
+    pub case_collisions: BTreeSet<BTreeSet<String>>,
+    /// Assets missing their .uasset/.umap or .uexp counterpart.
+    pub split_pairs: BTreeSet<String>,
+    /// Superclass path -> subclass paths.
+    pub hierarchy: BTreeMap<String, BTreeSet<String>>,
+    /// Inheritance cycles in `hierarchy`, only possible with corrupt or malicious assets.
+    pub hierarchy_cycles: Vec<Vec<String>>,
+    /// Package path -> packages it imports, e.g. `/Game/Foo` -> `/Game/Bar`, `/Script/Engine`.
+    pub dependencies: BTreeMap<String, BTreeSet<String>>,
+    pub assets: Vec<AssetVerdict>,
+    pub uexp_mismatches: BTreeSet<UexpMismatch>,
+    pub engine_version_mismatches: BTreeSet<EngineVersionMismatch>,
+    pub class_changes: BTreeSet<ClassChange>,
+    /// Only checked when linting against a [`GameIndex`].
+    pub unresolved_imports: BTreeSet<UnresolvedImport>,
+    /// Overrides shipping no .ubulk although the game's copy of the asset has one. This is a
+    /// heuristic: bulk data flags are not read, so overrides storing all mips or audio inline are
+    /// reported too. Only checked when linting against a [`GameIndex`].
+    pub missing_bulk_data: BTreeSet<String>,
+    /// Asset path without extension -> whether it overrides a game asset. Only populated when
+    /// linting against a [`GameIndex`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub origins: BTreeMap<String, Origin>,
+}
+
+/// Individual checks whose findings can be allowed, warned about or denied.
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialEq, PartialOrd, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    Extraneous,
+    SplitPairs,
+    AutoVerifyFail,
+    AutoVerifyUnknown,
+    NonPakFiles,
+    UexpMismatch,
+    EngineVersion,
+    ClassChange,
+    UnresolvedImport,
+    HierarchyCycle,
+    MountPoint,
+    CaseCollision,
+    MissingUbulk,
+}
+
+impl Check {
+    /// Name of the check as accepted by `--allow` and `--deny`.
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .expect("no check is skipped")
+            .get_name()
+            .to_owned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckLevels {
+    pub default: Level,
+    pub overrides: BTreeMap<Check, Level>,
+}
+
+impl Default for CheckLevels {
+    fn default() -> Self {
+        Self {
+            default: Level::Warn,
+            overrides: Default::default(),
+        }
+    }
+}
+
+impl CheckLevels {
+    pub fn set(&mut self, checks: &[Check], level: Level) {
+        for check in checks {
+            self.overrides.insert(*check, level);
+        }
+    }
+    pub fn get(&self, check: Check) -> Level {
+        self.overrides.get(&check).copied().unwrap_or(self.default)
+    }
+
+    /// Highest level of the given checks, or [`Level::Allow`] if there are none.
+    pub fn highest(&self, checks: &BTreeSet<Check>) -> Level {
+        checks
+            .iter()
+            .map(|c| self.get(*c))
+            .max()
+            .unwrap_or(Level::Allow)
+    }
+}
+
+impl LintReport {
+    pub fn class_trees(&self) -> Vec<Node> {
+        build_trees(&self.hierarchy)
+    }
+
+    /// Checks that produced at least one finding.
+    pub fn triggered_checks(&self) -> BTreeSet<Check> {
+        let mut checks = BTreeSet::new();
+        if !self.extraneous_files.is_empty() {
+            checks.insert(Check::Extraneous);
+        }
+        if !self.split_pairs.is_empty() {
+            checks.insert(Check::SplitPairs);
+        }
+        if !self.uexp_mismatches.is_empty() {
+            checks.insert(Check::UexpMismatch);
+        }
+        if !self.engine_version_mismatches.is_empty() {
+            checks.insert(Check::EngineVersion);
+        }
+        if !self.class_changes.is_empty() {
+            checks.insert(Check::ClassChange);
+        }
+        if !self.unresolved_imports.is_empty() {
+            checks.insert(Check::UnresolvedImport);
+        }
+        if !self.hierarchy_cycles.is_empty() {
+            checks.insert(Check::HierarchyCycle);
+        }
+        if self.mount_point.is_some() {
+            checks.insert(Check::MountPoint);
+        }
+        if !self.case_collisions.is_empty() {
+            checks.insert(Check::CaseCollision);
+        }
+        if !self.missing_bulk_data.is_empty() {
+            checks.insert(Check::MissingUbulk);
+        }
+        for asset in &self.assets {
+            match asset.auto_verify {
+                AutoVerify::Pass => {}
+                AutoVerify::Fail => {
+                    checks.insert(Check::AutoVerifyFail);
+                }
+                AutoVerify::Unknown => {
+                    checks.insert(Check::AutoVerifyUnknown);
+                }
+            }
+        }
+        checks
+    }
+
+    pub fn level(&self, levels: &CheckLevels) -> Level {
+        levels.highest(&self.triggered_checks())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PakReport {
+    pub name: String,
+    #[serde(flatten)]
+    pub report: LintReport,
+}
+
+/// Lint results for every pak shipped by a single mod.
+#[derive(Debug, Default, Serialize)]
+pub struct ModReport {
+    /// Archive members that are not paks.
+    pub non_pak_files: BTreeSet<String>,
+    pub paks: Vec<PakReport>,
+}
+
+impl ModReport {
+    pub fn triggered_checks(&self) -> BTreeSet<Check> {
+        let mut checks = BTreeSet::new();
+        if !self.non_pak_files.is_empty() {
+            checks.insert(Check::NonPakFiles);
+        }
+        for pak in &self.paks {
+            checks.extend(pak.report.triggered_checks());
+        }
+        checks
+    }
+
+    pub fn level(&self, levels: &CheckLevels) -> Level {
+        levels.highest(&self.triggered_checks())
+    }
+}
+
+/// Bumped whenever a field of the JSON report is renamed, removed or changes meaning.
+pub const REPORT_SCHEMA_VERSION: u32 = 3;
+
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    pub schema_version: u32,
+    pub mods: Vec<JsonModReport<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonModReport<'a> {
+    pub source: &'a str,
+    /// Highest level of any triggered check, absent if the mod could not be linted.
+    pub level: Option<Level>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub report: Option<&'a ModReport>,
+}
+
+impl<'a> JsonReport<'a> {
+    pub fn new(
+        results: impl IntoIterator<Item = (&'a str, &'a Result<ModReport>)>,
+        levels: &CheckLevels,
+    ) -> Self {
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            mods: results
+                .into_iter()
+                .map(|(source, result)| match result {
+                    Ok(report) => JsonModReport {
+                        source,
+                        level: Some(report.level(levels)),
+                        error: None,
+                        report: Some(report),
+                    },
+                    Err(e) => JsonModReport {
+                        source,
+                        level: None,
+                        error: Some(format!("{e:#}")),
+                        report: None,
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    pub policy: Policy,
+    /// Engine version of the game, used to parse unversioned assets and to flag assets
+    /// saved by other engine versions.
+    pub engine_version: EngineVersion,
+    pub index: Option<GameIndex>,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            policy: Default::default(),
+            engine_version: EngineVersion::VER_UE4_27,
+            index: None,
+        }
+    }
+}
+
+pub fn lint_mod(files: ModFiles, options: &LintOptions) -> Result<ModReport> {
+    let mut report = ModReport {
+        non_pak_files: files.other_files.into_iter().collect(),
+        ..Default::default()
+    };
+    for (name, pak) in files.paks {
+        let lint =
+            lint_pak(|| pak.open(), options).with_context(|| format!("failed to lint {name}"))?;
+        report.paks.push(PakReport { name, report: lint });
+    }
+    Ok(report)
+}
+
+/// Lints a single pak. `open` may be called many times while reading assets in parallel and
+/// each call must return an independent reader.
+pub fn lint_pak<R, F>(open: F, options: &LintOptions) -> Result<LintReport>
+where
+    R: Read + Seek,
+    F: Fn() -> Result<R> + Sync + Send,
+{
+    let policy = &options.policy;
+    let pak = PakBuilder::new().reader(&mut open()?)?;
+    let sanitized = resolve_mount_point(pak.mount_point())?;
+
+    let mut report = LintReport::default();
+    if pak.mount_point() != "../../../" {
+        report.mount_point = Some(pak.mount_point().to_owned());
+    }
+
+    let mut extensions: BTreeMap<String, BTreeSet<String>> = Default::default();
+    let mut case_insensitive: BTreeMap<String, BTreeSet<String>> = Default::default();
+    for f in pak.files() {
+        let full_path = sanitized.join(&f);
+        if policy.is_extraneous(full_path.as_str()) {
+            report.extraneous_files.insert(full_path.to_string());
+        }
+        case_insensitive
+            .entry(full_path.as_str().to_lowercase())
+            .or_default()
+            .insert(full_path.to_string());
+        let path = PakPath::new(&f);
+        if let Some(ext) = path.extension() {
+            extensions
+                .entry(path.with_extension("").to_string())
+                .or_default()
+                .insert(ext.to_owned());
+        }
+    }
+
+    report.case_collisions = case_insensitive
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect();
+
+    if let Some(index) = &options.index {
+        for f in extensions.keys() {
+            let path = sanitized.join(f).to_string();
+            let origin = index.origin(&path);
+            report.origins.insert(path, origin);
+        }
+    }
+
+    // packages shipped by the pak itself, as lowercase /Game/... paths
+    let packages = extensions
+        .iter()
+        .filter(|(_, ext)| ext.contains("uasset") || ext.contains("umap"))
+        .filter_map(|(f, _)| pak_path_to_game_path(sanitized.join(f)).ok())
+        .map(|p| p.to_lowercase())
+        .collect::<BTreeSet<_>>();
+
+    let mut jobs = vec![];
+    for (f, ext) in extensions {
+        let uasset = ext.contains("uasset");
+        let umap = ext.contains("umap");
+        let uexp = ext.contains("uexp");
+        let ubulk = ext.contains("ubulk");
+        if (umap || uasset) != uexp || (ubulk && !(umap || uasset)) {
+            for e in ext {
+                report
+                    .split_pairs
+                    .insert(sanitized.join(&f).with_extension(e).to_string());
+            }
+        } else if (umap || uasset) && uexp {
+            let asset_file = if uasset {
+                format!("{f}.uasset")
+            } else {
+                format!("{f}.umap")
+            };
+            let uexp_file = format!("{f}.uexp");
+            if let Some(index) = &options.index {
+                let path = sanitized.join(&f);
+                let original = index.get(path.as_str());
+                if !ubulk && original.is_some_and(|a| a.files.contains_key("ubulk")) {
+                    report
+                        .missing_bulk_data
+                        .insert(pak_path_to_game_path(&path).unwrap_or_else(|_| path.to_string()));
+                }
+            }
+            jobs.push((f, asset_file, uexp_file));
+        }
+    }
+
+    let assets = jobs
+        .par_iter()
+        .map_init(open, |reader, (f, asset_file, uexp_file)| {
+            let pak_path = sanitized.join(f);
+            let asset = reader
+                .as_mut()
+                .map_err(|e| anyhow!("{e:#}"))
+                .and_then(|reader| {
+                    read_asset(
+                        &pak,
+                        reader,
+                        asset_file,
+                        uexp_file,
+                        &pak_path,
+                        options.engine_version,
+                    )
+                });
+            // record failures against the asset instead of giving up on the whole pak
+            let asset = asset.unwrap_or_else(|e| AssetInfo {
+                path: pak_path_to_game_path(&pak_path).unwrap_or_else(|_| pak_path.to_string()),
+                parent_path: None,
+                class: Err(e),
+                export_error: None,
+                uexp_size: None,
+                package_version: None,
+                package_imports: vec![],
+            });
+            (pak_path.to_string(), asset)
+        })
+        .collect::<Vec<_>>();
+
+    for (pak_path, asset) in assets {
+        if let Some(index) = &options.index {
+            for import in &asset.package_imports {
+                if !import.starts_with("/Game/") || packages.contains(&import.to_lowercase()) {
+                    continue;
+                }
+                let found = game_path_to_pak_path(import)
+                    .map(|p| index.get(&p).is_some())
+                    .unwrap_or(false);
+                if !found {
+                    report.unresolved_imports.insert(UnresolvedImport {
+                        path: asset.path.clone(),
+                        import: import.clone(),
+                    });
+                }
+            }
+        }
+        if let Ok(package) = pak_path_to_game_path(&pak_path) {
+            report
+                .dependencies
+                .insert(package, asset.package_imports.iter().cloned().collect());
+        }
+        if let (Some(index), Ok(class)) = (&options.index, &asset.class) {
+            if let Some(original_class) = index.get(&pak_path).and_then(|a| a.class.as_ref()) {
+                if original_class != class {
+                    report.class_changes.insert(ClassChange {
+                        path: asset.path.clone(),
+                        original_class: original_class.clone(),
+                        class: class.clone(),
+                    });
+                }
+            }
+        }
+        if let Some(parent_path) = asset.parent_path {
+            report
+                .hierarchy
+                .entry(parent_path)
+                .or_default()
+                .insert(asset.path.clone());
+        }
+        if let Some((expected_size, actual_size)) = asset.uexp_size {
+            if expected_size != actual_size {
+                report.uexp_mismatches.insert(UexpMismatch {
+                    path: asset.path.clone(),
+                    expected_size,
+                    actual_size,
+                });
+            }
+        }
+        if let Some(version) = asset.package_version {
+            if !version.is_unversioned() && !version.matches(options.engine_version) {
+                report
+                    .engine_version_mismatches
+                    .insert(EngineVersionMismatch {
+                        path: asset.path.clone(),
+                        version,
+                        detected: version.detect(),
+                    });
+            }
+        }
+        let auto_verify = match &asset.class {
+            Ok(t) => {
+                if policy.auto_verified_classes.contains(t) {
+                    AutoVerify::Pass
+                } else {
+                    AutoVerify::Fail
+                }
+            }
+            _ => AutoVerify::Unknown,
+        };
+        let class = match asset.class {
+            Ok(t) => AssetType::Known(t),
+            Err(e) => AssetType::Unknown(format!("{e:#}")),
+        };
+        report.assets.push(AssetVerdict {
+            auto_verify,
+            class,
+            path: asset.path,
+            export_error: asset.export_error,
+        });
+    }
+    report.assets.sort();
+    report.hierarchy_cycles = find_cycles(&report.hierarchy);
+
+    Ok(report)
+}
+
+struct AssetInfo {
+    /// Full object path of the root export.
+    path: String,
+    parent_path: Option<String>,
+    class: Result<String>,
+    export_error: Option<String>,
+    /// Expected and actual .uexp size.
+    uexp_size: Option<(u64, u64)>,
+    package_version: Option<PackageVersion>,
+    package_imports: Vec<String>,
+}
+
+fn read_asset<R: Read + Seek>(
+    pak: &PakReader,
+    reader: &mut R,
+    asset_file: &str,
+    uexp_file: &str,
+    pak_path: &PakPath,
+    engine_version: EngineVersion,
+) -> Result<AssetInfo> {
+    let uasset = pak.get(asset_file, reader)?;
+    let uexp = pak.get(uexp_file, reader)?;
+
+    let path = pak_path_to_game_path(pak_path)?;
+
+    let package_version = PackageVersion::read(&uasset).ok();
+    let header_size = uasset.len() as u64;
+    let uexp_len = uexp.len() as u64;
+
+    let full = unreal_asset::Asset::new(
+        Cursor::new(uasset.clone()),
+        Some(Cursor::new(uexp)),
+        engine_version,
+        None,
+        false,
+    );
+    // fall back to the header alone so class and hierarchy are still available when the
+    // export data is malformed or uses something the parser does not support
+    let (asset, export_error) = match full {
+        Ok(asset) => (asset, None),
+        Err(e) => (
+            unreal_asset::Asset::new(Cursor::new(uasset), None, engine_version, None, true)
+                .context("failed to parse asset")?,
+            Some(e.to_string()),
+        ),
+    };
+
+    Ok(AssetInfo {
+        path: get_full_path(&path, &asset)?,
+        parent_path: get_parent_path(&asset)?,
+        class: get_type(&asset),
+        export_error,
+        uexp_size: expected_uexp_size(&asset, header_size).map(|expected| (expected, uexp_len)),
+        package_version,
+        package_imports: get_package_imports(&asset),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_names_match_cli_and_json() {
+        for check in Check::value_variants() {
+            let name = check.name();
+            assert_eq!(Check::from_str(&name, false), Ok(*check));
+            assert_eq!(serde_json::to_value(check).unwrap(), name.as_str());
+        }
+        assert_eq!(Check::MissingUbulk.name(), "missing-ubulk");
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_POLICY: &str = include_str!("default_policy.toml");
+
+/// Auto-verification policy. Loaded from a TOML or JSON file, falling back to the bundled default.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    pub auto_verified_classes: BTreeSet<String>,
+    pub valid_extensions: BTreeSet<String>,
+    pub ignored_paths: BTreeSet<String>,
+    /// Assets (paths relative to `../../../` without extension) that any number of mods may
+    /// ship without it being reported as a conflict.
+    #[serde(default)]
+    pub benign_conflicts: BTreeSet<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_POLICY).expect("bundled policy is valid")
+    }
+}
+
+impl Policy {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read policy {}", path.display()))?;
+        if path.extension().filter(|e| e == &"json").is_some() {
+            serde_json::from_str(&data)
+                .with_context(|| format!("failed to parse policy {}", path.display()))
+        } else {
+            toml::from_str(&data)
+                .with_context(|| format!("failed to parse policy {}", path.display()))
+        }
+    }
+
+    /// Whether a file (path relative to `../../../`) should not be shipped in a mod pak.
+    pub fn is_extraneous(&self, path: &str) -> bool {
+        if self.ignored_paths.contains(path) {
+            return false;
+        }
+        match typed_path::Utf8UnixPath::new(path).extension() {
+            Some(ext) => !self.valid_extensions.contains(ext),
+            None => true,
+        }
+    }
+}
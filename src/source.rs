@@ -0,0 +1,217 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{anyhow, bail, Result};
+use tempfile::NamedTempFile;
+
+/// A pak on disk that can be opened any number of times, e.g. for every parallel job reading it.
+#[derive(Debug)]
+pub enum PakFile {
+    Path(PathBuf),
+    /// Pak extracted from an archive, deleted when dropped.
+    Temp(NamedTempFile),
+}
+
+impl PakFile {
+    pub fn open(&self) -> Result<BufReader<File>> {
+        Ok(BufReader::new(match self {
+            PakFile::Path(path) => File::open(path)?,
+            PakFile::Temp(temp) => temp.reopen()?,
+        }))
+    }
+}
+
+pub struct ModFiles {
+    pub paks: Vec<(String, PakFile)>,
+    /// Archive members that are not paks (readmes, signatures, IoStore containers, ...).
+    pub other_files: Vec<String>,
+}
+
+fn modio_url_regex() -> regex::Regex {
+    regex::Regex::new(r"^https?://(mod\.io/g/drg/m/|drg\.(old\.)?mod\.io/)(?P<name_id>[^/#]+)$")
+        .unwrap()
+}
+
+pub fn is_modio_url(url: &str) -> bool {
+    modio_url_regex().is_match(url)
+}
+
+/// Recursively collect every .pak and .zip below `dir`, sorted by path.
+pub fn find_mod_files<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(find_mod_files(&path)?);
+        } else if path
+            .extension()
+            .filter(|e| e == &"pak" || e == &"zip")
+            .is_some()
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub fn get_mod(url: &str) -> Result<ModFiles> {
+    let re = modio_url_regex();
+
+    let path = if let Some(captures) = re.captures(url) {
+        let name_id = captures.name("name_id").unwrap().as_str();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .enable_io()
+            .build()
+            .unwrap()
+            .block_on(async { get_modio_mod(name_id).await })?
+    } else {
+        PathBuf::from(url)
+    };
+
+    get_mod_from_path(path)
+}
+
+pub fn get_mod_from_path(path: PathBuf) -> Result<ModFiles> {
+    let mut data = BufReader::new(File::open(&path)?);
+    if let Ok(mut archive) = zip::ZipArchive::new(&mut data) {
+        let mut files = ModFiles {
+            paks: vec![],
+            other_files: vec![],
+        };
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            match file.enclosed_name() {
+                Some(p) if p.extension().filter(|e| e == &"pak").is_some() => {
+                    let name = p.to_string_lossy().to_string();
+                    // extract to a temp file rather than memory as paks can be large
+                    let mut temp = NamedTempFile::new()?;
+                    std::io::copy(&mut file, &mut temp)?;
+                    files.paks.push((name, PakFile::Temp(temp)));
+                }
+                _ => files.other_files.push(file.name().to_owned()),
+            }
+        }
+        if files.paks.is_empty() {
+            bail!("Zip does not contain pak");
+        }
+        Ok(files)
+    } else {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        Ok(ModFiles {
+            paks: vec![(name, PakFile::Path(path))],
+            other_files: vec![],
+        })
+    }
+}
+
+pub fn get_modio_key() -> Result<String> {
+    use directories::BaseDirs;
+    let key_path = if let Some(base_dirs) = BaseDirs::new() {
+        let dir = base_dirs.config_dir().join(env!("CARGO_PKG_NAME"));
+        Some((dir.join("modio_key.txt"), dir))
+    } else {
+        eprintln!("could not determine config path to save key");
+        None
+    };
+
+    let key = key_path.as_ref().and_then(|p| {
+        std::fs::read_to_string(&p.0)
+            .ok()
+            .map(|k| k.trim().to_owned())
+    });
+    Ok(if let Some(key) = key {
+        key
+    } else {
+        eprintln!("No saved modio API key found, please generate one by going to https://mod.io/me/access#api and pasting it here");
+        let key = rpassword::prompt_password("API key: ")?;
+        if let Some(key_path) = key_path {
+            std::fs::create_dir_all(&key_path.1)?;
+            eprintln!("writing modio API key to {}", key_path.0.display());
+            std::fs::write(key_path.0, &key)?;
+        }
+        key
+    })
+}
+
+pub const MODIO_DRG_ID: u32 = 2475;
+
+/// Shared by all downloads so concurrent progress bars do not overwrite each other.
+fn download_progress() -> &'static indicatif::MultiProgress {
+    static PROGRESS: OnceLock<indicatif::MultiProgress> = OnceLock::new();
+    PROGRESS.get_or_init(indicatif::MultiProgress::new)
+}
+
+async fn get_modio_mod(name_id: &str) -> Result<PathBuf> {
+    let modio = modio::Modio::new(modio::Credentials::new(get_modio_key()?))?;
+
+    use modio::filter::Eq;
+
+    let mut mods = modio
+        .game(MODIO_DRG_ID)
+        .mods()
+        .search(modio::mods::filters::NameId::eq(name_id))
+        .collect()
+        .await?;
+    if mods.len() > 1 {
+        Err(anyhow!(
+            "multiple mods returned for mod name_id {}",
+            name_id,
+        ))
+    } else if let Some(mod_) = mods.pop() {
+        let file = mod_
+            .modfile
+            .ok_or_else(|| anyhow!("mod {name_id} does not have an associated modfile"))?;
+
+        // several mods may be downloaded at once and upload filenames are not unique
+        std::fs::create_dir_all(name_id)?;
+        let filename = Path::new(name_id).join(&file.filename);
+        download_progress().suspend(|| {
+            eprintln!(
+                "downloading mod {} file_id={} to {}...",
+                name_id,
+                file.id,
+                filename.display()
+            )
+        });
+
+        use futures_util::TryStreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        let download_bar = download_progress().add(indicatif::ProgressBar::new(file.filesize));
+        download_bar.set_style(indicatif::ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?.progress_chars("#>-"));
+
+        let mut stream = Box::pin(
+            modio
+                .download(modio::download::DownloadAction::FileObj(Box::new(file)))
+                .stream(),
+        );
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&filename)
+            .await?;
+        while let Some(bytes) = stream.try_next().await? {
+            file.write_all(&bytes).await?;
+            download_bar.inc(bytes.len() as u64);
+        }
+        file.flush().await?;
+        download_bar.finish();
+
+        Ok(filename)
+    } else {
+        Err(anyhow!("no mods returned for mod name_id {}", &name_id))
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use unreal_asset::{
+    engine_version::{get_object_versions, EngineVersion},
+    object_version::ObjectVersionUE5,
+};
+
+const PACKAGE_FILE_TAG: u32 = 0x9E2A83C1;
+
+/// Engine releases accepted by `--engine-version`. The object versions each one saves packages
+/// with come from unreal_asset's own mapping.
+const RELEASES: &[(&str, EngineVersion)] = &[
+    ("4.0", EngineVersion::VER_UE4_0),
+    ("4.1", EngineVersion::VER_UE4_1),
+    ("4.2", EngineVersion::VER_UE4_2),
+    ("4.3", EngineVersion::VER_UE4_3),
+    ("4.4", EngineVersion::VER_UE4_4),
+    ("4.5", EngineVersion::VER_UE4_5),
+    ("4.6", EngineVersion::VER_UE4_6),
+    ("4.7", EngineVersion::VER_UE4_7),
+    ("4.8", EngineVersion::VER_UE4_8),
+    ("4.9", EngineVersion::VER_UE4_9),
+    ("4.10", EngineVersion::VER_UE4_10),
+    ("4.11", EngineVersion::VER_UE4_11),
+    ("4.12", EngineVersion::VER_UE4_12),
+    ("4.13", EngineVersion::VER_UE4_13),
+    ("4.14", EngineVersion::VER_UE4_14),
+    ("4.15", EngineVersion::VER_UE4_15),
+    ("4.16", EngineVersion::VER_UE4_16),
+    ("4.17", EngineVersion::VER_UE4_17),
+    ("4.18", EngineVersion::VER_UE4_18),
+    ("4.19", EngineVersion::VER_UE4_19),
+    ("4.20", EngineVersion::VER_UE4_20),
+    ("4.21", EngineVersion::VER_UE4_21),
+    ("4.22", EngineVersion::VER_UE4_22),
+    ("4.23", EngineVersion::VER_UE4_23),
+    ("4.24", EngineVersion::VER_UE4_24),
+    ("4.25", EngineVersion::VER_UE4_25),
+    ("4.26", EngineVersion::VER_UE4_26),
+    ("4.27", EngineVersion::VER_UE4_27),
+    ("5.0", EngineVersion::VER_UE5_0),
+    ("5.1", EngineVersion::VER_UE5_1),
+    ("5.2", EngineVersion::VER_UE5_2),
+];
+
+/// UE4 and (for UE5 releases) UE5 object versions packages are saved with.
+fn object_versions(engine_version: EngineVersion) -> (i32, Option<i32>) {
+    let (ue4, ue5) = get_object_versions(engine_version);
+    let ue5 = ue5 as i32;
+    (
+        ue4 as i32,
+        (ue5 >= ObjectVersionUE5::INITIAL_VERSION as i32).then_some(ue5),
+    )
+}
+
+/// Parses an engine release such as `4.27` (or `UE4.27`).
+pub fn parse_engine_version(s: &str) -> Result<EngineVersion> {
+    let version = s.trim_start_matches("UE");
+    RELEASES
+        .iter()
+        .find(|r| r.0 == version)
+        .map(|r| r.1)
+        .ok_or_else(|| {
+            anyhow!(
+                "unknown engine version {s}, expected one of {}",
+                RELEASES.iter().map(|r| r.0).collect::<Vec<_>>().join(", ")
+            )
+        })
+}
+
+/// Version fields from the start of a package file summary.
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialEq, PartialOrd, Serialize)]
+pub struct PackageVersion {
+    pub file_version_ue4: i32,
+    pub file_version_ue5: Option<i32>,
+    pub file_version_licensee: i32,
+}
+
+impl PackageVersion {
+    pub fn read(data: &[u8]) -> Result<Self> {
+        let mut fields = data
+            .chunks_exact(4)
+            .map(|c| i32::from_le_bytes(c.try_into().unwrap()));
+        let mut next = || {
+            fields
+                .next()
+                .ok_or_else(|| anyhow!("truncated package summary"))
+        };
+
+        if next()? as u32 != PACKAGE_FILE_TAG {
+            bail!("not a package file");
+        }
+        let legacy_file_version = next()?;
+        if legacy_file_version != -4 {
+            next()?; // legacy UE3 version
+        }
+        let file_version_ue4 = next()?;
+        let file_version_ue5 = if legacy_file_version <= -8 {
+            Some(next()?)
+        } else {
+            None
+        };
+        let file_version_licensee = next()?;
+        Ok(Self {
+            file_version_ue4,
+            file_version_ue5,
+            file_version_licensee,
+        })
+    }
+
+    /// Unversioned packages are saved with all version fields zeroed and cannot be checked.
+    pub fn is_unversioned(&self) -> bool {
+        self.file_version_ue4 == 0 && self.file_version_licensee == 0
+    }
+
+    fn saved_by(&self, engine_version: EngineVersion) -> bool {
+        let (ue4, ue5) = object_versions(engine_version);
+        self.file_version_ue4 == ue4 && self.file_version_ue5.filter(|v| *v != 0) == ue5
+    }
+
+    /// Whether this package was saved by the given engine release.
+    pub fn matches(&self, engine_version: EngineVersion) -> bool {
+        RELEASES
+            .iter()
+            .find(|r| r.1 == engine_version)
+            .map(|r| self.saved_by(r.1))
+            .unwrap_or(true)
+    }
+
+    /// Engine releases that save packages with this version, e.g. `4.22/4.23/4.24`. Releases
+    /// sharing an object version cannot be told apart, notably 4.26 and 4.27 (both 522).
+    pub fn detect(&self) -> Option<String> {
+        let candidates = RELEASES
+            .iter()
+            .filter(|r| self.saved_by(r.1))
+            .map(|r| r.0)
+            .collect::<Vec<_>>();
+        (!candidates.is_empty()).then(|| candidates.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Start of a package file summary, followed by padding as in a real header.
+    fn header(legacy_file_version: i32, ue4: i32, ue5: Option<i32>, licensee: i32) -> Vec<u8> {
+        let mut fields = vec![PACKAGE_FILE_TAG as i32, legacy_file_version];
+        if legacy_file_version != -4 {
+            fields.push(864);
+        }
+        fields.push(ue4);
+        fields.extend(ue5);
+        fields.extend([licensee, 0, 0]);
+        fields.into_iter().flat_map(i32::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn ue4_27() {
+        let version = PackageVersion::read(&header(-7, 522, None, 0)).unwrap();
+        assert_eq!(
+            version,
+            PackageVersion {
+                file_version_ue4: 522,
+                file_version_ue5: None,
+                file_version_licensee: 0,
+            }
+        );
+        assert!(!version.is_unversioned());
+        assert!(version.matches(EngineVersion::VER_UE4_27));
+        assert!(!version.matches(EngineVersion::VER_UE5_1));
+        // 4.26 saves the same object version
+        assert_eq!(version.detect().as_deref(), Some("4.26/4.27"));
+    }
+
+    #[test]
+    fn ue5_1() {
+        let version = PackageVersion::read(&header(-8, 522, Some(1008), 0)).unwrap();
+        assert_eq!(version.file_version_ue4, 522);
+        assert_eq!(version.file_version_ue5, Some(1008));
+        assert_eq!(version.file_version_licensee, 0);
+        assert!(version.matches(EngineVersion::VER_UE5_1));
+        assert!(!version.matches(EngineVersion::VER_UE4_27));
+        assert_eq!(version.detect().as_deref(), Some("5.1"));
+    }
+
+    #[test]
+    fn unversioned() {
+        let version = PackageVersion::read(&header(-8, 0, Some(0), 0)).unwrap();
+        assert!(version.is_unversioned());
+        assert_eq!(version.detect(), None);
+    }
+
+    #[test]
+    fn legacy_version_without_ue3_version() {
+        let version = PackageVersion::read(&header(-4, 214, None, 3)).unwrap();
+        assert_eq!(version.file_version_ue4, 214);
+        assert_eq!(version.file_version_licensee, 3);
+    }
+
+    #[test]
+    fn invalid_headers() {
+        assert!(PackageVersion::read(&[0; 32]).is_err());
+        assert!(PackageVersion::read(&header(-7, 522, None, 0)[..12]).is_err());
+    }
+}